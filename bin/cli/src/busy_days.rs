@@ -0,0 +1,120 @@
+use calendar::ics_import::{self, BusyTime};
+use chrono::{Days, NaiveDate, NaiveTime};
+use config::settings::Config;
+use db::exclude_days;
+
+// Days after today are at most 30 working days away, which is always within twice as many calendar days
+const LOOK_AHEAD_DAYS: u64 = 60;
+
+// How busy each day is from the calendar, used to lower what is expected on busy days
+pub struct BusyDays {
+    busy: Vec<BusyTime>,
+    window: (NaiveTime, NaiveTime),
+}
+
+impl BusyDays {
+    pub fn new(busy: Vec<BusyTime>, window: (NaiveTime, NaiveTime)) -> BusyDays {
+        BusyDays { busy, window }
+    }
+
+    // Without a calendar configured no day is busy, a calendar that can't be read is an error
+    // Recurring events are expanded over the floating week and the furthest any command looks ahead
+    pub fn load(config: &Config, today: NaiveDate) -> Result<BusyDays, String> {
+        let first = today.checked_sub_days(Days::new(6)).unwrap();
+        let last = today.checked_add_days(Days::new(LOOK_AHEAD_DAYS)).unwrap();
+        let busy = match &config.calendar.busy_path {
+            Some(path) => ics_import::load_busy_times(path, first, last)?,
+            None => Vec::new(),
+        };
+        Ok(BusyDays::new(busy, config.time_block.window().expect("The time block window should be validated")))
+    }
+
+    pub fn busy_minutes(&self, day: NaiveDate) -> i32 {
+        ics_import::busy_minutes(&self.busy, day, self.window.0, self.window.1)
+    }
+
+    fn window_minutes(&self) -> i32 {
+        (self.window.1 - self.window.0).num_minutes() as i32
+    }
+
+    // Scale a target down by the share of the working window that is free, rounding up so a part free day still counts
+    pub fn scale(&self, value: i32, day: NaiveDate) -> i32 {
+        let window = self.window_minutes();
+        let free = (window - self.busy_minutes(day)).max(0);
+        if value <= 0 || free >= window {
+            value
+        }
+        else {
+            (value * free + window - 1) / window
+        }
+    }
+
+    pub fn is_full(&self, day: NaiveDate) -> bool {
+        self.busy_minutes(day) >= self.window_minutes()
+    }
+
+    // The fully booked days in the floating week that aren't already excluded, when configured to exclude them
+    // These are only excluded while working out the week so nothing is saved
+    pub fn full_days(&self, config: &Config, today: NaiveDate, excluded: &[NaiveDate]) -> Vec<NaiveDate> {
+        if !config.calendar.exclude_full_days {
+            return Vec::new();
+        }
+        (0..7)
+            .map(|i| today.checked_sub_days(Days::new(i)).unwrap())
+            .filter(|d| self.is_full(*d) && !excluded.contains(d))
+            .collect()
+    }
+}
+
+// The excluded days along with the fully booked ones, for working out the floating week
+pub fn excluded_days(busy_days: &BusyDays, config: &Config, today: NaiveDate) -> Vec<NaiveDate> {
+    let mut excluded = exclude_days::get_excluded_days(&config.db).expect("Failed to load excluded days");
+    excluded.extend(busy_days.full_days(config, today, &excluded));
+    excluded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn busy_days() -> BusyDays {
+        let window = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+        BusyDays::new(vec![
+            BusyTime { start: at(4, 9), end: at(4, 15) },
+            BusyTime { start: at(5, 8), end: at(5, 18) },
+        ], window)
+    }
+
+    #[test]
+    fn test_targets_scale_with_free_time() {
+        let b = busy_days();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 8, d).unwrap();
+        // A quarter of the day is free
+        assert_eq!(b.scale(8, day(4)), 2);
+        assert_eq!(b.scale(5, day(4)), 2);
+        assert_eq!(b.scale(8, day(5)), 0);
+        assert_eq!(b.scale(8, day(6)), 8);
+    }
+
+    #[test]
+    fn test_full_days() {
+        let b = busy_days();
+        assert!(!b.is_full(NaiveDate::from_ymd_opt(2025, 8, 4).unwrap()));
+        assert!(b.is_full(NaiveDate::from_ymd_opt(2025, 8, 5).unwrap()));
+    }
+
+    #[test]
+    fn test_full_days_in_the_floating_week() {
+        let b = busy_days();
+        let mut config = Config::default();
+        let today = NaiveDate::from_ymd_opt(2025, 8, 8).unwrap();
+        assert!(b.full_days(&config, today, &[]).is_empty());
+        config.calendar.exclude_full_days = true;
+        assert_eq!(b.full_days(&config, today, &[]), vec![NaiveDate::from_ymd_opt(2025, 8, 5).unwrap()]);
+        assert!(b.full_days(&config, today, &[NaiveDate::from_ymd_opt(2025, 8, 5).unwrap()]).is_empty());
+    }
+}
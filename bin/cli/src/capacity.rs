@@ -0,0 +1,109 @@
+use api::filter_tasks::{self, Task};
+use chrono::NaiveDate;
+use config::settings::{CapacityConfig, Config};
+use db::postpone_counts;
+
+use crate::ordering;
+use crate::protection::Protection;
+use crate::reschedule;
+use crate::task_tree::{self, TaskTree};
+use crate::working_days::WorkingDays;
+
+// The effort a task takes, from its duration or the configured default
+pub fn task_minutes(t: &Task, config: &CapacityConfig) -> i32 {
+    t.duration.as_ref().map(|d| d.minutes()).unwrap_or(config.default_task_minutes)
+}
+
+pub fn tree_minutes(tree: &TaskTree, config: &CapacityConfig) -> i32 {
+    tree.tasks.iter().map(|t| task_minutes(t, config)).sum()
+}
+
+// Postpone tasks off today until the total effort fits in the time available
+pub async fn plan(key: &str, config: &Config, today: NaiveDate) {
+    let todays_tasks = filter_tasks::get_todays_tasks(key, &config.api, today).await;
+    let available = config.capacity.minutes_on(today);
+    let planned: i32 = todays_tasks.iter().map(|t| task_minutes(t, &config.capacity)).sum();
+    println!("Planned {planned} minutes of {available} available today");
+    if planned <= available {
+        println!("Today fits, not postponing any tasks");
+        return;
+    }
+
+    // Tasks at a set time are blocked out so stay, as do protected tasks
+    let candidates = Protection::new(&config.protect).unprotected(todays_tasks.iter()
+        .filter(|t| !t.due.date.has_time())
+        .filter(|t| !reschedule::is_left_in_place(t, config.recurring.postpone)));
+    // Subtasks move with their parent so only whole trees can be moved
+    let mut trees = task_tree::movable_trees(task_tree::build_trees(todays_tasks.iter()), &candidates);
+    let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+    ordering::sort_trees(&mut trees, &config.postpone, &counts);
+    let to_postpone = select_to_postpone(trees, planned - available, &config.capacity);
+
+    let working_days = WorkingDays::load(config);
+    let freed: i32 = to_postpone.iter().map(|tree| tree_minutes(tree, &config.capacity)).sum();
+    for t in to_postpone.iter().flat_map(|tree| tree.tasks.iter()) {
+        reschedule::postpone_task_to_tomorrow(key, config, &working_days, t, config.recurring.postpone).await;
+    }
+    let remaining = planned - freed;
+    if remaining > available {
+        println!("Planned {remaining} minutes of {available} available today, the rest can't be moved");
+    }
+    else {
+        println!("Planned {remaining} minutes of {available} available today");
+    }
+}
+
+// The lowest priority trees go first, then the configured order, skipping any that take no time
+fn select_to_postpone<'a>(mut trees: Vec<TaskTree<'a>>, over: i32, config: &CapacityConfig) -> Vec<TaskTree<'a>> {
+    // The sort is stable so the configured order is kept within a priority
+    trees.sort_by_key(|tree| tree.tasks.iter().map(|t| t.priority).max().unwrap_or(0));
+    let mut selected: Vec<TaskTree> = Vec::new();
+    let mut freed = 0;
+    for tree in trees.into_iter() {
+        if freed >= over {
+            break;
+        }
+        let minutes = tree_minutes(&tree, config);
+        if minutes > 0 {
+            freed += minutes;
+            selected.push(tree);
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{minutes, task};
+
+    fn ids(trees: &[TaskTree]) -> Vec<String> {
+        trees.iter().map(|tree| tree.root().id.clone()).collect()
+    }
+
+    #[test]
+    fn test_default_minutes_for_tasks_without_duration() {
+        let config = CapacityConfig { default_task_minutes: 20, ..CapacityConfig::default() };
+        assert_eq!(task_minutes(&Task { duration: minutes(45), ..task("a") }, &config), 45);
+        assert_eq!(task_minutes(&task("a"), &config), 20);
+    }
+
+    #[test]
+    fn test_lowest_priority_postponed_until_it_fits() {
+        let tasks = [
+            Task { priority: 4, duration: minutes(60), ..task("a") },
+            Task { duration: minutes(30), ..task("b") },
+            Task { duration: minutes(30), ..task("c") },
+            Task { priority: 2, duration: minutes(60), ..task("d") },
+        ];
+        let selected = select_to_postpone(task_tree::build_trees(tasks.iter()), 50, &CapacityConfig::default());
+        assert_eq!(ids(&selected), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_tasks_taking_no_time_are_left() {
+        let tasks = [task("a"), Task { duration: minutes(30), ..task("b") }];
+        let selected = select_to_postpone(task_tree::build_trees(tasks.iter()), 10, &CapacityConfig::default());
+        assert_eq!(ids(&selected), vec!["b"]);
+    }
+}
@@ -0,0 +1,21 @@
+use config::settings::Config;
+use db::postpone_counts;
+use std::process;
+
+// The tasks postponed the most, a sign they should be broken down, rescheduled properly or dropped
+pub fn report(limit: usize, config: &Config) {
+    let most_postponed = postpone_counts::get_most_postponed(limit, &config.db).unwrap_or_else(|e| {
+        eprintln!("Failed to load postpone counts: {e}");
+        process::exit(1)
+    });
+    if most_postponed.is_empty() {
+        println!("No tasks have been postponed yet");
+        return;
+    }
+    for c in most_postponed.iter() {
+        println!("{count:>4}  {content} (last postponed {day})",
+            count = c.count,
+            content = c.content,
+            day = c.last_postponed.format(&config.formats.date));
+    }
+}
@@ -0,0 +1,51 @@
+use config::config_manager;
+use config::settings::Config;
+use std::path::Path;
+use std::process;
+
+pub fn show(path: &Path, config_result: Result<Config, String>) {
+    let config = config_result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1)
+    });
+    println!("# Config file: {path}", path = path.display());
+    println!("{}", toml::to_string_pretty(&config).expect("Config should always serialize"));
+}
+
+pub fn validate(path: &Path, config_result: Result<Config, String>) {
+    let config = config_result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1)
+    });
+    match config.validate() {
+        Ok(()) => println!("Config is valid ({path})", path = path.display()),
+        Err(errors) => {
+            for e in errors.iter() {
+                eprintln!("{e}");
+            }
+            process::exit(1)
+        }
+    }
+}
+
+// Show each profile with where its token comes from and the database it uses
+pub fn list_profiles(overrides: &[String]) {
+    let named = config_manager::list_profiles().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1)
+    });
+    let mut profiles: Vec<Option<&str>> = vec![None];
+    profiles.extend(named.iter().map(|p| Some(p.as_str())));
+    for profile in profiles.iter() {
+        let name = profile.unwrap_or("default");
+        let path = config_manager::config_path(*profile);
+        match config_manager::load(*profile, None, overrides) {
+            Ok(config) => println!("{name}: token from {source:?}, {url}, database {database} ({path})",
+                source = config.token.source,
+                url = config.api.base_url,
+                database = config.db.database,
+                path = path.display()),
+            Err(e) => println!("{name}: {e}"),
+        }
+    }
+}
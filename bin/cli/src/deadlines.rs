@@ -0,0 +1,51 @@
+use api::filter_tasks::Task;
+use chrono::NaiveDate;
+use config::settings::DeadlinePolicy;
+
+// The date a task can be postponed to without passing its deadline, None when it should stay where it is
+pub fn limit_to_deadline(t: &Task, date: NaiveDate, policy: DeadlinePolicy) -> Option<NaiveDate> {
+    let Some(deadline) = t.deadline.as_ref().and_then(|d| d.date()) else {
+        return Some(date);
+    };
+    if date <= deadline {
+        return Some(date);
+    }
+    match policy {
+        // Only cap if that still moves the task later
+        DeadlinePolicy::Cap => Some(deadline).filter(|d| *d > t.due.date.date()),
+        DeadlinePolicy::Refuse => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{deadline, due, task};
+    use api::filter_tasks::DueDate;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_no_deadline_or_before_it_is_unchanged() {
+        assert_eq!(limit_to_deadline(&task("1"), day(9), DeadlinePolicy::Refuse), Some(day(9)));
+        assert_eq!(limit_to_deadline(&Task { deadline: deadline("2025-08-09"), ..task("1") }, day(9), DeadlinePolicy::Refuse), Some(day(9)));
+    }
+
+    #[test]
+    fn test_cap_moves_to_the_deadline() {
+        assert_eq!(limit_to_deadline(&Task { deadline: deadline("2025-08-06"), ..task("1") }, day(9), DeadlinePolicy::Cap), Some(day(6)));
+    }
+
+    #[test]
+    fn test_cap_at_the_deadline_stays() {
+        let t = Task { due: due(DueDate::Date(day(6))), deadline: deadline("2025-08-06"), ..task("1") };
+        assert_eq!(limit_to_deadline(&t, day(9), DeadlinePolicy::Cap), None);
+    }
+
+    #[test]
+    fn test_refuse_stays() {
+        assert_eq!(limit_to_deadline(&Task { deadline: deadline("2025-08-06"), ..task("1") }, day(9), DeadlinePolicy::Refuse), None);
+    }
+}
@@ -0,0 +1,95 @@
+use api::comments;
+use api::filter_tasks::Task;
+use api::update_task::{self, TaskUpdate};
+use config::settings::{Config, EscalationConfig};
+
+// The highest priority Todoist supports, shown as p1 in the apps
+const MAX_PRIORITY: i32 = 4;
+
+// The changes to make to a task that has been postponed too many times
+#[derive(Debug, Default, PartialEq)]
+pub struct Escalation {
+    pub priority: Option<i32>,
+    pub labels: Option<Vec<String>>,
+    pub project_id: Option<String>,
+    pub comment: Option<String>,
+}
+
+// Escalation only happens once, when the count reaches the configured number
+pub fn escalation_for(config: &EscalationConfig, t: &Task, count: i32) -> Option<Escalation> {
+    if config.after != Some(count) {
+        return None;
+    }
+    Some(Escalation {
+        priority: Some(t.priority + 1)
+            .filter(|_| config.raise_priority)
+            .filter(|p| *p <= MAX_PRIORITY),
+        labels: config.add_label.as_ref()
+            .filter(|l| !t.labels.contains(l))
+            .map(|l| t.labels.iter().cloned().chain([l.to_owned()]).collect()),
+        project_id: config.move_to_project.to_owned()
+            .filter(|p| *p != t.project_id),
+        comment: config.comment.as_ref().map(|c| c.replace("{count}", &count.to_string())),
+    })
+}
+
+pub async fn escalate(key: &str, config: &Config, t: &Task, count: i32) {
+    let Some(escalation) = escalation_for(&config.escalation, t, count) else {
+        return;
+    };
+    println!("Escalating {content} after {count} postpones", content = t.content);
+    if escalation.priority.is_some() || escalation.labels.is_some() {
+        let update = TaskUpdate {
+            priority: escalation.priority,
+            labels: escalation.labels,
+            ..TaskUpdate::default()
+        };
+        update_task::update_task(key, &config.api, &t.id, &update).await;
+    }
+    if let Some(project_id) = escalation.project_id {
+        update_task::move_task(key, &config.api, &t.id, &project_id).await;
+    }
+    if let Some(comment) = escalation.comment {
+        comments::add_comment(key, &config.api, &t.id, &comment).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{labels, task};
+
+    fn config() -> EscalationConfig {
+        EscalationConfig {
+            after: Some(5),
+            raise_priority: true,
+            add_label: Some("postponed".to_string()),
+            move_to_project: Some("someday".to_string()),
+            comment: Some("Postponed {count} times".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_only_escalates_at_the_threshold() {
+        assert_eq!(escalation_for(&config(), &task("1"), 4), None);
+        assert_eq!(escalation_for(&config(), &task("1"), 6), None);
+        assert_eq!(escalation_for(&EscalationConfig::default(), &task("1"), 5), None);
+    }
+
+    #[test]
+    fn test_escalation_changes() {
+        assert_eq!(escalation_for(&config(), &Task { labels: labels(&["home"]), ..task("1") }, 5), Some(Escalation {
+            priority: Some(2),
+            labels: Some(vec!["home".to_string(), "postponed".to_string()]),
+            project_id: Some("someday".to_string()),
+            comment: Some("Postponed 5 times".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_nothing_already_applied_is_repeated() {
+        let escalation = escalation_for(&config(), &Task { priority: 4, labels: labels(&["postponed"]), ..task("1") }, 5).unwrap();
+        assert_eq!(escalation.priority, None);
+        assert_eq!(escalation.labels, None);
+    }
+}
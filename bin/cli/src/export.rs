@@ -0,0 +1,53 @@
+use api::filter_tasks::{self, DueDate, Task};
+use calendar::ics_export::{self, Item, When};
+use chrono::{Days, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use config::settings::Config;
+use std::path::Path;
+
+// Write today's and the upcoming days' tasks to a calendar file
+pub async fn export(key: &str, config: &Config, today: NaiveDate, path: &Path) {
+    let mut tasks: Vec<Task> = Vec::new();
+    for i in 0..=config.calendar.export_days {
+        let day = today.checked_add_days(Days::new(i)).unwrap();
+        tasks.extend(filter_tasks::get_tasks_due_on(key, &config.api, day).await);
+    }
+    let items: Vec<Item> = tasks.iter().map(|t| item(t, config)).collect();
+    // The stamp is the start of today so regenerating with the same tasks gives the same file
+    let midnight = today.and_time(NaiveTime::MIN);
+    let stamp = Local.from_local_datetime(&midnight).earliest().map(|d| d.with_timezone(&Utc)).unwrap_or(midnight.and_utc());
+    match ics_export::write_ics(path, &items, stamp) {
+        Ok(true) => println!("Exported {count} tasks to {path}", count = items.len(), path = path.display()),
+        Ok(false) => println!("The calendar at {path} is already up to date", path = path.display()),
+        Err(e) => panic!("{e}"),
+    }
+}
+
+fn item(t: &Task, config: &Config) -> Item {
+    let minutes = t.duration.as_ref().map(|d| d.minutes()).unwrap_or(config.time_block.default_minutes);
+    let when = match &t.due.date {
+        DueDate::Date(d) => When::Day(*d),
+        DueDate::Floating(d) => When::Floating(*d, minutes),
+        DueDate::Fixed(d) => When::Fixed(d.with_timezone(&Utc), minutes),
+    };
+    Item {
+        id: t.id.clone(),
+        summary: t.content.clone(),
+        description: Some(format!("https://app.todoist.com/app/task/{}", t.id)),
+        when,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{due, task};
+
+    #[test]
+    fn test_timed_tasks_get_the_default_length() {
+        let day = NaiveDate::from_ymd_opt(2025, 8, 4).unwrap();
+        let start = day.and_hms_opt(9, 0, 0).unwrap();
+        let config = Config::default();
+        assert_eq!(item(&Task { due: due(DueDate::Date(day)), ..task("1") }, &config).when, When::Day(day));
+        assert_eq!(item(&Task { due: due(DueDate::Floating(start)), ..task("1") }, &config).when, When::Floating(start, config.time_block.default_minutes));
+    }
+}
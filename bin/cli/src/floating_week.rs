@@ -0,0 +1,99 @@
+use api::completed_fetch::CompletedStats;
+use chrono::NaiveDate;
+use std::cmp;
+
+// Progress over the last seven days, ignoring any excluded days
+#[derive(Debug, PartialEq)]
+pub struct FloatingWeek {
+    pub completed: i32,
+    pub target: i32,
+    pub active_days: i32,
+}
+
+impl FloatingWeek {
+    // The tasks still needed for the week, capped at the daily cap to avoid over clogging days
+    pub fn remaining(&self, daily_cap: i32) -> i32 {
+        cmp::min(self.target - self.completed, daily_cap)
+    }
+}
+
+pub fn calculate_progress_on_floating_week(stats: &CompletedStats, excluded_days: &[NaiveDate]) -> FloatingWeek {
+    let excluded: Vec<String> = excluded_days.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
+    let active_items: Vec<i32> = stats.days_items.iter()
+            .filter(|x| !excluded.contains(&x.date)) // Filter out any excluded days
+            .map(|x| x.total_completed)
+            .collect();
+    let active_days = active_items.len() as i32;
+    FloatingWeek {
+        completed: active_items.iter().sum(),
+        target: scale_weekly_goal(stats.goals.weekly_goal, active_days),
+        active_days,
+    }
+}
+
+// Scale the weekly goal down to the number of days that count, rounding up so a part day still needs a task
+pub fn scale_weekly_goal(weekly_goal: i32, active_days: i32) -> i32 {
+    if active_days >= 7 {
+        weekly_goal
+    }
+    else if active_days <= 0 {
+        0
+    }
+    else {
+        (weekly_goal * active_days + 6) / 7
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::completed_fetch::{DayItem, Goals};
+
+    fn stats_for_week(completed: [i32; 7]) -> CompletedStats {
+        CompletedStats {
+            days_items: completed.iter().enumerate().map(|(i, c)| DayItem {
+                date: format!("2025-07-{:02}", 21 + i),
+                total_completed: *c,
+            }).collect(),
+            week_items: Vec::new(),
+            goals: Goals { daily_goal: 5, weekly_goal: 35 },
+        }
+    }
+
+    #[test]
+    fn test_no_exclusions_sums_all_days() {
+        let week = calculate_progress_on_floating_week(&stats_for_week([5, 5, 5, 5, 5, 5, 5]), &[]);
+        assert_eq!(week, FloatingWeek { completed: 35, target: 35, active_days: 7 });
+    }
+
+    #[test]
+    fn test_excluded_day_is_not_counted() {
+        let excluded = [NaiveDate::from_ymd_opt(2025, 7, 22).unwrap()];
+        let week = calculate_progress_on_floating_week(&stats_for_week([5, 0, 5, 5, 5, 5, 5]), &excluded);
+        assert_eq!(week, FloatingWeek { completed: 30, target: 30, active_days: 6 });
+    }
+
+    #[test]
+    fn test_exclusion_outside_week_is_ignored() {
+        let excluded = [NaiveDate::from_ymd_opt(2025, 7, 1).unwrap()];
+        let week = calculate_progress_on_floating_week(&stats_for_week([1, 2, 3, 4, 5, 6, 7]), &excluded);
+        assert_eq!(week, FloatingWeek { completed: 28, target: 35, active_days: 7 });
+    }
+
+    #[test]
+    fn test_scaled_goal_rounds_up() {
+        assert_eq!(scale_weekly_goal(20, 7), 20);
+        assert_eq!(scale_weekly_goal(20, 6), 18);
+        assert_eq!(scale_weekly_goal(20, 1), 3);
+        assert_eq!(scale_weekly_goal(20, 0), 0);
+    }
+
+    #[test]
+    fn test_remaining_is_capped_daily() {
+        let week = FloatingWeek { completed: 20, target: 35, active_days: 7 };
+        assert_eq!(week.remaining(5), 5);
+        assert_eq!(week.remaining(20), 15);
+        let done = FloatingWeek { completed: 40, target: 35, active_days: 7 };
+        assert_eq!(done.remaining(5), -5);
+    }
+}
@@ -0,0 +1,113 @@
+use api::filter_tasks;
+use chrono::NaiveDate;
+use config::settings::Config;
+
+use crate::working_days::WorkingDays;
+
+// The number of tasks already due on a day
+#[derive(Debug, PartialEq)]
+pub struct DayLoad {
+    pub day: NaiveDate,
+    pub count: i32,
+}
+
+// Count the tasks on each of the upcoming working days after today
+pub async fn upcoming_loads(key: &str, config: &Config, working_days: &WorkingDays, today: NaiveDate) -> Vec<DayLoad> {
+    let days: Vec<NaiveDate> = (1..=config.postpone.horizon_days)
+        .map(|i| working_days.add_working_days(today, i))
+        .collect();
+    loads_on(key, config, &days).await
+}
+
+// Count the tasks already due on each of the days
+pub async fn loads_on(key: &str, config: &Config, days: &[NaiveDate]) -> Vec<DayLoad> {
+    let mut loads: Vec<DayLoad> = Vec::new();
+    for day in days.iter().copied() {
+        let count = filter_tasks::get_tasks_due_on(key, &config.api, day).await.len() as i32;
+        log::debug!("Found {count} tasks already due on {day}");
+        loads.push(DayLoad { day, count });
+    }
+    loads
+}
+
+// Assign each task to the least loaded day under the cap, earliest first on a tie
+// Once every day is at the cap the least loaded day is still used so no task is left behind
+pub fn assign(loads: &mut [DayLoad], tasks: usize, cap: i32) -> Vec<NaiveDate> {
+    assign_with_caps(loads, &vec![1; tasks], |_| cap)
+}
+
+// The same as assign for items that each take a number of tasks, such as a task and its subtasks, with a cap for each day
+pub fn assign_with_caps(loads: &mut [DayLoad], sizes: &[i32], cap: impl Fn(NaiveDate) -> i32) -> Vec<NaiveDate> {
+    let mut assigned: Vec<NaiveDate> = Vec::new();
+    if loads.is_empty() {
+        return assigned;
+    }
+    for size in sizes.iter() {
+        let under_cap = loads.iter_mut()
+            .filter(|l| l.count + size <= cap(l.day))
+            .min_by_key(|l| (l.count, l.day));
+        let chosen = match under_cap {
+            Some(l) => l,
+            None => loads.iter_mut().min_by_key(|l| (l.count, l.day)).unwrap(),
+        };
+        chosen.count += size;
+        assigned.push(chosen.day);
+    }
+    assigned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loads(counts: &[i32]) -> Vec<DayLoad> {
+        counts.iter().enumerate().map(|(i, c)| DayLoad {
+            day: NaiveDate::from_ymd_opt(2025, 8, 4 + i as u32).unwrap(),
+            count: *c,
+        }).collect()
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_least_loaded_day_is_used_first() {
+        let mut l = loads(&[3, 1, 2]);
+        assert_eq!(assign(&mut l, 3, 5), vec![day(5), day(5), day(6)]);
+    }
+
+    #[test]
+    fn test_ties_go_to_the_earliest_day() {
+        let mut l = loads(&[0, 0, 0]);
+        assert_eq!(assign(&mut l, 4, 5), vec![day(4), day(5), day(6), day(4)]);
+    }
+
+    #[test]
+    fn test_full_days_are_skipped() {
+        let mut l = loads(&[5, 4, 0]);
+        assert_eq!(assign(&mut l, 2, 2), vec![day(6), day(6)]);
+    }
+
+    #[test]
+    fn test_overflow_when_every_day_is_full() {
+        let mut l = loads(&[2, 3]);
+        assert_eq!(assign(&mut l, 2, 2), vec![day(4), day(4)]);
+        assert_eq!(l[0].count, 4);
+    }
+
+    #[test]
+    fn test_each_day_has_its_own_cap() {
+        let mut l = loads(&[0, 0]);
+        assert_eq!(assign_with_caps(&mut l, &[1, 1, 1], |d| if d == day(4) { 1 } else { 5 }), vec![day(4), day(5), day(5)]);
+    }
+
+    #[test]
+    fn test_items_are_charged_by_their_size() {
+        let mut l = loads(&[0, 1]);
+        // The first day only has room for the single task once the tree of three is on it
+        assert_eq!(assign_with_caps(&mut l, &[3, 2, 1], |_| 4), vec![day(4), day(5), day(4)]);
+        assert_eq!(l[0].count, 4);
+        assert_eq!(l[1].count, 3);
+    }
+}
@@ -1,12 +1,44 @@
+mod busy_days;
+mod capacity;
+mod chronic;
+mod config_commands;
+mod deadlines;
+mod escalation;
+mod export;
+mod floating_week;
+mod load_balance;
+mod ordering;
+mod overdue;
+mod priority_balance;
 mod productivity_mode;
-
-use api::{completed_fetch, filter_tasks, update_task, update_goals};
-use db::{exclude_days, exclude_weeks};
-use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, Weekday};
-use clap::Parser;
+mod protection;
+mod pull_forward;
+mod rebalance;
+mod recurring;
+mod reschedule;
+mod task_tree;
+#[cfg(test)]
+mod test_support;
+mod time_block;
+mod token_commands;
+mod triage;
+mod wip;
+mod working_days;
+
+use api::{completed_fetch, filter_tasks, update_goals, user};
+use config::config_manager;
+use config::settings::Config;
+use db::{exclude_days, exclude_weeks, postpone_counts};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use clap::{Parser, Subcommand};
 use std::string::ToString;
 use std::cmp;
-use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+use token::{encrypted_store, redact, token_provider};
+use busy_days::BusyDays;
+use protection::Protection;
+use working_days::WorkingDays;
 
 // Command line arguments
 #[derive(Parser, Debug)]
@@ -20,7 +52,7 @@ struct Args {
     #[arg(short, long)]
     update_goals: bool,
 
-    /// Postpone tasks assigned to today to tomorrow
+    /// Postpone tasks assigned to today to the next working day
     #[arg(long)]
     postpone: bool,
 
@@ -28,11 +60,11 @@ struct Args {
     #[arg(long)]
     postpone_to_goal: bool,
 
-    /// Postpone all low priority tomorrow tasks by a number of days.
+    /// Postpone all low priority tomorrow tasks by a number of working days.
     #[arg(long)]
     postpone_by_days: Option<i8>,
 
-    /// Bring overdue to today
+    /// Bring overdue tasks back into the schedule, by default to today, see the overdue config for other policies
     #[arg(short, long)]
     overdue: bool,
 
@@ -44,7 +76,7 @@ struct Args {
     #[arg(long)]
     exclude_day_shown: bool,
 
-    /// A week you want to exclude from the weekly goal calculation, should be date of Monday, in format YYYY-MM-DD
+    /// A week you want to exclude from the weekly goal calculation, should be the date of the first day of the week, in format YYYY-MM-DD
     #[arg(long)]
     exclude_week: Option<String>,
 
@@ -52,13 +84,99 @@ struct Args {
     #[arg(long)]
     exclude_week_shown: bool,
 
-    /// Purge all the current saved data, useful to delete the saved api key and any excluded days/weeks
+    /// Purge all the current saved data, useful to delete the stored api key and any excluded days/weeks
     #[arg(long)]
     purge: bool,
 
+    /// The named profile to use, each has its own config, token and database
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// A config file to use instead of the profile's file in the standard config directory
+    #[arg(long = "config", global = true)]
+    config_file: Option<PathBuf>,
+
+    /// Override a config value, in the format <section>.<key>=<value>, can be used multiple times
+    #[arg(long = "set", global = true)]
+    config_overrides: Vec<String>,
+
     /// Sets the verobosity of the logs to output
     #[command(flatten)]
     verbosity: clap_verbosity_flag::Verbosity,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Manage the named profiles
+    Profiles {
+        #[command(subcommand)]
+        action: ProfilesAction,
+    },
+    /// Manage the locally encrypted API token store
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+    /// Postpone tasks off today until their total duration fits in the time available
+    Plan,
+    /// Give today's tasks without a time a start time in the working window, around breaks and tasks already at a time
+    TimeBlock {
+        /// Set the times in Todoist rather than only showing them
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Pull tasks from the upcoming working days into today until it has enough to meet the weekly goal
+    PullForward,
+    /// Postpone tasks off today until it is within the WIP limit, in the configured order
+    CapToday,
+    /// Give undated tasks from the Inbox, or the triage filter, a due date on the days under target for the week
+    Triage,
+    /// Move the lowest ranked high priority tasks off days over the priority limits to the next day with room
+    BalancePriorities,
+    /// Even out the tasks on the upcoming working days toward each day's target, leaving tasks at a time, with a deadline or protected in place
+    Rebalance,
+    /// Export today's and the upcoming days' tasks to an .ics file, timed tasks as events and the rest as all day to-dos
+    Export {
+        /// The file to write, defaults to calendar.export_path
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    /// Report the tasks that have been postponed the most
+    Chronic {
+        /// The number of tasks to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the configuration after applying the file, environment and command line overrides
+    Show,
+    /// Check the configuration can be loaded and all values are valid
+    Validate,
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfilesAction {
+    /// List the default and named profiles
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum TokenAction {
+    /// Encrypt and save a token read from stdin, used by the store token source
+    Store,
+    /// Remove the stored token and its key
+    Clear,
 }
 
 #[tokio::main]
@@ -66,45 +184,166 @@ async fn main() -> Result<(), reqwest::Error> {
     let args = Args::parse();
     env_logger::Builder::new()
         .filter_level(args.verbosity.into())
+        // Any registered secret, such as the API token, is removed from log output
+        .format(|buf, record| {
+            writeln!(buf, "[{} {} {}] {}", buf.timestamp(), record.level(), record.target(), redact::redact(&record.args().to_string()))
+        })
         .init();
-    
-    let key_var = env::var("TODOIST_API_KEY");
-    if key_var.is_err() {
-        panic!("You need to set the environment variable TODOIST_API_KEY with your API key")
+
+    if let Some(Command::Profiles { action: ProfilesAction::List }) = &args.command {
+        config_commands::list_profiles(&args.config_overrides);
+        return Ok(());
+    }
+
+    let config_result = config_manager::load(args.profile.as_deref(), args.config_file.as_deref(), &args.config_overrides);
+    if let Some(Command::Config { action }) = &args.command {
+        let config_path = args.config_file.clone().unwrap_or_else(|| config_manager::config_path(args.profile.as_deref()));
+        match action {
+            ConfigAction::Show => config_commands::show(&config_path, config_result),
+            ConfigAction::Validate => config_commands::validate(&config_path, config_result),
+        }
+        return Ok(());
+    }
+    let config: Config = config_result.unwrap_or_else(|e| panic!("{e}"));
+    if let Err(errors) = config.validate() {
+        panic!("Invalid config: {}", errors.join(", "));
+    }
+
+    if let Some(Command::Token { action }) = &args.command {
+        match action {
+            TokenAction::Store => token_commands::store(&config.db),
+            TokenAction::Clear => token_commands::clear(&config.db),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Chronic { limit }) = &args.command {
+        chronic::report(*limit, &config);
+        return Ok(());
+    }
+
+    let token = token_provider::get_token(&config.token, &config.db).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+    let key = token.expose().to_owned();
+
+    let today:NaiveDate = config.day.today();
+
+    if let Some(Command::PullForward) = &args.command {
+        pull_forward::pull_forward(&key, &config, today).await;
+        return Ok(());
+    }
+
+    if let Some(Command::TimeBlock { apply }) = &args.command {
+        time_block::time_block(&key, &config, today, *apply).await;
+        return Ok(());
     }
-    let key = key_var.unwrap();
 
-    let today:NaiveDate = Local::now().naive_local().date();
+    if let Some(Command::Plan) = &args.command {
+        capacity::plan(&key, &config, today).await;
+        return Ok(());
+    }
+
+    if let Some(Command::CapToday) = &args.command {
+        wip::cap_today(&key, &config, today, config.recurring.postpone).await;
+        return Ok(());
+    }
+
+    if let Some(Command::Triage) = &args.command {
+        triage::triage(&key, &config, today).await;
+        return Ok(());
+    }
+
+    if let Some(Command::BalancePriorities) = &args.command {
+        priority_balance::balance_priorities(&key, &config, today).await;
+        return Ok(());
+    }
+
+    if let Some(Command::Rebalance) = &args.command {
+        rebalance::rebalance(&key, &config, today).await;
+        return Ok(());
+    }
+
+    if let Some(Command::Export { path }) = &args.command {
+        match path.as_ref().or(config.calendar.export_path.as_ref()) {
+            Some(path) => export::export(&key, &config, today, path).await,
+            None => println!("No file to export to, set calendar.export_path or use --path"),
+        }
+        return Ok(());
+    }
 
     if args.status {
         if args.update_goals && (args.exclude_day_shown || args.exclude_week_shown) {
             panic!("Cannot use --update-goals with either exclude shown commands");
         }
 
-        let stats: completed_fetch::CompletedStats = completed_fetch::get_completed_stats(&key).await;
+        let stats: completed_fetch::CompletedStats = completed_fetch::get_completed_stats(&key, &config.api).await;
+
+        // Load any days to exclude from daily goal calculation
+        let days_result = exclude_days::get_excluded_days(&config.db);
+        if days_result.is_err() {
+            panic!()
+        }
+        let mut excluded_days: Vec<NaiveDate> = days_result.unwrap();
+        // Fully booked days in the calendar don't count towards the week
+        let busy_days = BusyDays::load(&config, today).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1)
+        });
+        for day in busy_days.full_days(&config, today, &excluded_days) {
+            println!("Excluding fully booked day {day}", day = day.format(&config.formats.date));
+            excluded_days.push(day);
+        }
+        let days : Vec<String> = excluded_days.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
 
         // Floating week progress
-        let sum_of_tasks: i32 = calculate_progress_on_floating_week(&stats);
+        let week_progress = floating_week::calculate_progress_on_floating_week(&stats, &excluded_days);
 
         println!("Daily Progress: {done} / {goal}",
             done = stats.days_items.iter().find(|x| x.date == today.to_string()).unwrap().total_completed,
             goal = stats.goals.daily_goal);
 
         println!("Weekly Progress: {done} / {goal}",
-            done = sum_of_tasks,
-            goal = stats.goals.weekly_goal);
+            done = week_progress.completed,
+            goal = week_progress.target);
+        if week_progress.active_days < 7 {
+            println!("Weekly goal scaled to {days} active days", days = week_progress.active_days);
+        }
 
         // Check what mode you should be operating in
         let done_today = stats.days_items.iter().find(|x| x.date == today.format("%Y-%m-%d").to_string()).unwrap();
-        let mode = productivity_mode::calculate_mode(sum_of_tasks, stats.goals.weekly_goal, stats.goals.daily_goal, done_today.total_completed);
+        let mode = productivity_mode::calculate_mode(week_progress.completed, week_progress.target, stats.goals.daily_goal, done_today.total_completed);
         println!("Mode: {mode}!", mode = mode);
 
-        // Load any days to exclude from daily goal calculation
-        let days_result = exclude_days::get_excluded_days();
-        if days_result.is_err() {
-            panic!()
+        let busy_minutes = busy_days.busy_minutes(today);
+        if busy_minutes > 0 {
+            println!("Busy for {busy_minutes} minutes today, aiming for at most {cap} tasks",
+                cap = busy_days.scale(config.daily_cap(stats.goals.weekly_goal), today));
+        }
+
+        // Show the tasks for today that won't be postponed by any rule
+        let todays_tasks = filter_tasks::get_todays_tasks(&key, &config.api, today).await;
+        let protected = Protection::new(&config.protect).protected(todays_tasks.iter());
+        if !protected.is_empty() {
+            println!("Protected tasks:");
+            for (t, reason) in protected.iter() {
+                println!("  {content}: {reason}", content = t.content);
+            }
+        }
+
+        // Flag any tasks that have already passed their deadline
+        let missed_deadlines = filter_tasks::get_missed_deadline_tasks(&key, &config.api, today).await;
+        if !missed_deadlines.is_empty() {
+            println!("Missed deadlines:");
+            for t in missed_deadlines.iter() {
+                let deadline = t.deadline.as_ref().and_then(|d| d.date());
+                match deadline {
+                    Some(d) => println!("  {content}: deadline was {day}", content = t.content, day = d.format(&config.formats.date)),
+                    None => println!("  {content}", content = t.content),
+                }
+            }
         }
-        let days : Vec<String> = days_result.unwrap().iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
 
         // Check whether to change daily goal
         let min_daily_option = stats.days_items.iter()
@@ -118,11 +357,11 @@ async fn main() -> Result<(), reqwest::Error> {
             else {
                 println!("New daily goal should be {new}, from {day}", new = min_daily.total_completed, day = min_daily.date);
                 if args.update_goals {
-                    update_goals::update_daily_goal(&key, &min_daily.total_completed).await;
+                    update_goals::update_daily_goal(&key, &config.api, &min_daily.total_completed).await;
                     println!("Updated daily goal to {new}", new = min_daily.total_completed);
                 }
                 if args.exclude_day_shown {
-                    exclude_days::exclude_day(NaiveDate::parse_from_str(&min_daily.date, "%Y-%m-%d").expect("Date is in the wrong format")).expect("Failed to write excluded day");
+                    exclude_days::exclude_day(NaiveDate::parse_from_str(&min_daily.date, "%Y-%m-%d").expect("Date is in the wrong format"), today, &config.db).expect("Failed to write excluded day");
                     println!("Excluded day {day}", day = min_daily.date)
                 }
             }
@@ -132,16 +371,17 @@ async fn main() -> Result<(), reqwest::Error> {
         }
 
         // Load any weeks to filter out from weekly goal calculation
-        let excluded_weeks: Vec<String> = exclude_weeks::get_excluded_weeks()
+        let excluded_weeks: Vec<String> = exclude_weeks::get_excluded_weeks(&config.db)
             .expect("Failed to load excluded weeks")
             .iter()
             .map(|d| d.format("%Y-%m-%d").to_string())
             .collect();
 
-        // Remove the latest item which will be for this week
+        // Remove the item for this week as it isn't finished, Todoist's weeks may start on a different day to the configured one
+        let week_start = resolve_week_start(&key, &config).await;
         let mut week_items = stats.week_items;
-        week_items.sort_by(|a,b| a.from.cmp(&b.from));
-        week_items.pop();
+        week_items.retain(|x| NaiveDate::parse_from_str(&x.from, "%Y-%m-%d")
+            .is_ok_and(|from| from.checked_add_days(Days::new(7)).unwrap() <= today));
 
         // Check whether to increase weekly goal
         let min_weekly = week_items.iter()
@@ -153,191 +393,191 @@ async fn main() -> Result<(), reqwest::Error> {
         else {
             println!("New weekly goal should be {new}, from {day}", new = min_weekly.total_completed, day = min_weekly.from);
             if args.update_goals {
-                update_goals::update_weekly_goal(&key, &min_weekly.total_completed).await;
+                update_goals::update_weekly_goal(&key, &config.api, &min_weekly.total_completed).await;
                 println!("Updated weekly goal to {new}", new = min_weekly.total_completed);
             }
             if args.exclude_week_shown {
-                exclude_weeks::exclude_week(NaiveDate::parse_from_str(&min_weekly.from, "%Y-%m-%d").expect("Date is in the wrong format")).expect("Failed to write excluded week");
+                exclude_weeks::exclude_week(NaiveDate::parse_from_str(&min_weekly.from, "%Y-%m-%d").expect("Date is in the wrong format"), today, week_start, &config.db).expect("Failed to write excluded week");
                 println!("Excluded week from {day}", day = min_weekly.from)
             }
         }
     }
     else if args.postpone {
-        let todays_tasks = filter_tasks::get_todays_tasks(&key).await;
-        println!("Found {} tasks to move to tomorrow", todays_tasks.len());
-        for t in todays_tasks.iter() {
-            postpone_task_to_tomorrow(&key, t).await;
+        let todays_tasks = filter_tasks::get_todays_tasks(&key, &config.api, today).await;
+        let working_days = WorkingDays::load(&config);
+        let unprotected = Protection::new(&config.protect).unprotected(todays_tasks.iter());
+        // Subtasks move with their parent so only whole trees can be moved
+        let to_postpone = task_tree::movable_trees(task_tree::build_trees(todays_tasks.iter()), &unprotected);
+        println!("Found {} tasks to move to the next working day", to_postpone.iter().map(|tree| tree.size(false)).sum::<i32>());
+        for t in to_postpone.iter().flat_map(|tree| tree.tasks.iter()) {
+            reschedule::postpone_task_to_tomorrow(&key, &config, &working_days, t, config.recurring.postpone).await;
         }
     }
     else if args.postpone_to_goal {
         // First reshedule all overdue tasks
-        overdue(&key).await;
+        overdue::overdue(&key, &config, today).await;
         // Get today tasks
-        let todays_tasks: Vec<filter_tasks::Task> = filter_tasks::get_todays_tasks(&key).await;
-        let total_today_tasks = todays_tasks.len() as i32;
+        let todays_tasks: Vec<filter_tasks::Task> = filter_tasks::get_todays_tasks(&key, &config.api, today).await;
+        // Subtasks can be counted along with their parent as a single task
+        let count_once = config.tasks.count_tree_once;
+        let today_trees = task_tree::build_trees(todays_tasks.iter());
+        let total_today_tasks: i32 = today_trees.iter().map(|tree| tree.size(count_once)).sum();
         println!("Found {} tasks for today", total_today_tasks);
         // Check if any need to be rescheduled
-        let stats: completed_fetch::CompletedStats = completed_fetch::get_completed_stats(&key).await;
-        let sum_of_tasks: i32 = calculate_progress_on_floating_week(&stats);
+        let stats: completed_fetch::CompletedStats = completed_fetch::get_completed_stats(&key, &config.api).await;
+        // Busy days in the calendar get a lower cap and fully booked ones don't count towards the week
+        let busy_days = BusyDays::load(&config, today).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1)
+        });
+        let excluded_days = busy_days::excluded_days(&busy_days, &config, today);
+        let week_progress = floating_week::calculate_progress_on_floating_week(&stats, &excluded_days);
+        let working_days = WorkingDays::load(&config);
+        let daily_cap = busy_days.scale(config.daily_cap(stats.goals.weekly_goal), today);
         // Take remaining tasks for week or maximum daily required to meet weekly goal to avoid over clogging days
-        let remaining_tasks_for_week = cmp::min(stats.goals.weekly_goal - sum_of_tasks, stats.goals.weekly_goal/7);
+        let remaining_tasks_for_week = week_progress.remaining(daily_cap);
         if remaining_tasks_for_week >= total_today_tasks {
             println!("The number of tasks is below or equal to the number needed to complete your week so not rescheduling any");
         }
         else {
-            // Filter out any tasks that have a higher priority + have a time to be done
-            let filter_tasks: Vec<&filter_tasks::Task>  = todays_tasks.iter()
-                .filter(|t| t.priority == 1)
+            // Filter out any tasks that have a higher priority + have a time to be done + are protected
+            let filter_tasks: Vec<&filter_tasks::Task>  = Protection::new(&config.protect).unprotected(todays_tasks.iter()
+                .filter(|t| t.priority == config.tasks.low_priority)
                 .filter(|t| t.duration.is_none())
-                .collect();
-            let low_priority_total = filter_tasks.len() as i32;
+                .filter(|t| !reschedule::is_left_in_place(t, config.recurring.postpone_to_goal)));
+            // Subtasks move with their parent so only whole trees can be moved
+            let mut movable_trees = task_tree::movable_trees(today_trees, &filter_tasks);
+            // Put the tasks to move first at the front
+            let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+            ordering::sort_trees(&mut movable_trees, &config.postpone, &counts);
+            let low_priority_total: i32 = movable_trees.iter().map(|tree| tree.size(count_once)).sum();
             // If no needed remaining tasks for the week then just move all filtered tasks OR if the remaining tasks is satisfied by the higher priority items
-            if remaining_tasks_for_week <= 0 || remaining_tasks_for_week <= total_today_tasks - low_priority_total {
+            let max_to_reschedule: i32 = if remaining_tasks_for_week <= 0 || remaining_tasks_for_week <= total_today_tasks - low_priority_total {
                 println!("Rescheduling all lower priority tasks");
-                let mut days = 1;
-                let mut count = 0;
-                for t in filter_tasks.iter() {
-                    postpone_task_by_days(&key, t, days).await;
-                    count += 1;
-                    if count >= remaining_tasks_for_week {
-                        days += 1;
-                        count = 0
-                    }
-                }
+                low_priority_total
             }
             else {
                 // Calculate the max to reschedule and then take that number of first set of elements
-                let max_to_reschedule: usize = (total_today_tasks - remaining_tasks_for_week) as usize;
+                let max_to_reschedule: i32 = total_today_tasks - remaining_tasks_for_week;
                 println!("Rescheduling at most {num} lower priority tasks", num = max_to_reschedule);
-                let mut days = 1;
-                let mut count = 0;
-                for t in filter_tasks.iter().take(max_to_reschedule) {
-                    postpone_task_by_days(&key, t, days).await;
-                    count += 1;
-                    if count >= remaining_tasks_for_week {
-                        days += 1;
-                        count = 0
-                    }
+                max_to_reschedule
+            };
+            // Take whole trees in order while they fit
+            let mut to_reschedule: Vec<task_tree::TaskTree> = Vec::new();
+            let mut taken = 0;
+            for tree in movable_trees.into_iter() {
+                if taken + tree.size(count_once) <= max_to_reschedule {
+                    taken += tree.size(count_once);
+                    to_reschedule.push(tree);
                 }
             }
+            // Spread the tasks over the upcoming days that have the fewest tasks already, keeping each tree on one day
+            let mut loads = load_balance::upcoming_loads(&key, &config, &working_days, today).await;
+            let max_per_day = config.postpone.max_tasks_per_day.unwrap_or(cmp::max(config.daily_cap(stats.goals.weekly_goal), 1));
+            let sizes: Vec<i32> = to_reschedule.iter().map(|tree| tree.size(count_once)).collect();
+            let assigned_days = load_balance::assign_with_caps(&mut loads, &sizes, |d| busy_days.scale(max_per_day, d));
+            for (tree, day) in to_reschedule.iter().zip(assigned_days) {
+                for t in tree.tasks.iter() {
+                    reschedule::postpone_task_to_date(&key, &config, t, day, config.recurring.postpone_to_goal).await;
+                }
+            }
+        }
+        // The WIP limit is a hard cap whatever the goal needs
+        if config.wip.is_limited() {
+            wip::cap_today(&key, &config, today, config.recurring.postpone_to_goal).await;
         }
         if args.update_goals {
             // Add on the number already achieved today
             let today = stats.days_items.iter()
                 .find(|x| x.date == today.format("%Y-%m-%d").to_string()).expect("Today should always exist"); // Find today's date
             // Take remaining for week + today OR maximum daily required to meet weekly goal to avoid over clogging days
-            let remaining_for_week_including_today = cmp::min(remaining_tasks_for_week + today.total_completed, stats.goals.weekly_goal/7);
+            let remaining_for_week_including_today = cmp::min(remaining_tasks_for_week + today.total_completed, daily_cap);
             if remaining_for_week_including_today <=0 {
                 println!("At the target! Setting a goal of 1");
-                update_goals::update_daily_goal(&key, &1).await;
+                update_goals::update_daily_goal(&key, &config.api, &1).await;
             }
             else {
                 println!("The number of tasks to aim for today is: {num}", num = remaining_for_week_including_today);
-                update_goals::update_daily_goal(&key, &remaining_for_week_including_today).await;
+                update_goals::update_daily_goal(&key, &config.api, &remaining_for_week_including_today).await;
             }
         }
+        // Keep the exported calendar in step with the new plan
+        if let Some(path) = &config.calendar.export_path {
+            export::export(&key, &config, today, path).await;
+        }
     }
     else if args.postpone_by_days.is_some() {
         // Get all tasks due tomorrow
-        let todays_tasks: Vec<filter_tasks::Task> = filter_tasks::get_tomorrow_tasks(&key).await;
+        let todays_tasks: Vec<filter_tasks::Task> = filter_tasks::get_tomorrow_tasks(&key, &config.api, today).await;
         // Filter to low priority tasks
-        let filter_tasks: Vec<&filter_tasks::Task>  = todays_tasks.iter()
-                .filter(|t| t.priority == 1)
+        let filter_tasks: Vec<&filter_tasks::Task>  = Protection::new(&config.protect).unprotected(todays_tasks.iter()
+                .filter(|t| t.priority == config.tasks.low_priority)
                 .filter(|t| t.duration.is_none())
-                .collect();
-        for t in filter_tasks.iter() {
-            postpone_task_by_days(&key, t, args.postpone_by_days.unwrap()).await;
+                .filter(|t| !reschedule::is_left_in_place(t, config.recurring.postpone_by_days)));
+        // Subtasks move with their parent so only whole trees can be moved
+        let mut movable_trees = task_tree::movable_trees(task_tree::build_trees(todays_tasks.iter()), &filter_tasks);
+        let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+        ordering::sort_trees(&mut movable_trees, &config.postpone, &counts);
+        let working_days = WorkingDays::load(&config);
+        for t in movable_trees.iter().flat_map(|tree| tree.tasks.iter()) {
+            reschedule::postpone_task_by_days(&key, &config, &working_days, t, args.postpone_by_days.unwrap(), config.recurring.postpone_by_days).await;
         }
     }
     else if args.overdue {
-        overdue(&key).await;
+        overdue::overdue(&key, &config, today).await;
+        if config.wip.is_limited() {
+            wip::cap_today(&key, &config, today, config.recurring.overdue).await;
+        }
     }
     else if args.exclude_day.is_some() {
-        let day = NaiveDate::parse_from_str(&args.exclude_day.unwrap().to_owned(), "%Y-%m-%d").unwrap();
-        let result = exclude_days::exclude_day(day);
+        let day = NaiveDate::parse_from_str(&args.exclude_day.unwrap().to_owned(), &config.formats.date).unwrap();
+        let result = exclude_days::exclude_day(day, today, &config.db);
         if result.is_err() {
             panic!()
         }
-        println!("Excluded day {day}", day = day)
+        println!("Excluded day {day}", day = day.format(&config.formats.date))
     }
     else if let Some(exclude_week) = args.exclude_week {
-        let day = NaiveDate::parse_from_str(&exclude_week.to_owned(), "%Y-%m-%d").unwrap();
-        // Check that the day is a Monday
-        if day.weekday() != Weekday::Mon {
-            println!("An excluded week date must be a Monday");
+        let day = NaiveDate::parse_from_str(&exclude_week.to_owned(), &config.formats.date).unwrap();
+        // Check that the day is the first day of the week
+        let week_start = resolve_week_start(&key, &config).await;
+        if day.weekday() != week_start {
+            println!("An excluded week date must be a {week_start}", week_start = weekday_name(week_start));
         }
         else {
-            let result = exclude_weeks::exclude_week(day);
+            let result = exclude_weeks::exclude_week(day, today, week_start, &config.db);
             if result.is_err() {
                 panic!()
             }
-            println!("Excluded week from {day}", day = day)
+            println!("Excluded week from {day}", day = day.format(&config.formats.date))
         }
     }
     else if args.purge {
-        exclude_days::purge().expect("Failed to exclude days store");
-        exclude_weeks::purge().expect("Failed to exclude weeks store");
+        exclude_days::purge(&config.db).expect("Failed to exclude days store");
+        exclude_weeks::purge(&config.db).expect("Failed to exclude weeks store");
+        postpone_counts::purge(&config.db).expect("Failed to purge postpone counts");
+        encrypted_store::purge(&config.db).expect("Failed to purge token store");
     }
 
     Ok(())
 }
 
-fn parse_due_date_time(due : &String) -> NaiveDateTime {
-    let due_date : NaiveDateTime = 
-    if due.contains("Z") {
-        NaiveDateTime::parse_from_str(&due.to_owned(), "%Y-%m-%dT%H:%M:%SZ").unwrap()
-    }
-    else {
-        NaiveDateTime::parse_from_str(&due.to_owned(), "%Y-%m-%dT%H:%M:%S").unwrap()
-    };
-    due_date
-}
-
-fn calculate_progress_on_floating_week(stats: &completed_fetch::CompletedStats) -> i32 {
-    stats.days_items.iter()
-            .map(|x| x.total_completed)
-            .sum()
-}
-
-async fn postpone_task_to_tomorrow(key: &str, t: &filter_tasks::Task) {
-    postpone_task_by_days(key, t, 1).await;
-}
-
-async fn postpone_task_by_days(key: &str, t: &filter_tasks::Task, days: i8) {
-    // If it contains a time then need to preserve that
-    if t.due.date.contains("T") {
-        let due_date_time : NaiveDateTime = parse_due_date_time(&t.due.date);
-        let new_due_date = due_date_time.checked_add_days(Days::new(days as u64)).unwrap();
-        update_task::update_task_due(key, &t.id, new_due_date.format("%Y-%m-%dT%H:%M:%S").to_string(), t.due.lang.to_owned(), t.due.string.to_owned()).await;
-        println!("Rescheduled {content} to {due}", content = t.content, due = new_due_date)
-    }
-    // If it is only a date 
-    else {
-        let due_date = NaiveDate::parse_from_str(&t.due.date.to_owned(), "%Y-%m-%d").unwrap();
-        let new_due_date = due_date.checked_add_days(Days::new(days as u64)).unwrap();
-        update_task::update_task_due(key, &t.id, new_due_date.format("%Y-%m-%d").to_string(), t.due.lang.to_owned(), t.due.string.to_owned()).await;
-        println!("Rescheduled {content} to {due}", content = t.content, due = new_due_date)
+// The configured week start, or the one from the Todoist user settings
+async fn resolve_week_start(key: &str, config: &Config) -> Weekday {
+    match config.week.start_day.weekday() {
+        Some(w) => w,
+        None => user::get_week_start(key, &config.api).await,
     }
 }
 
-async fn overdue(key: &str) {
-    let today:NaiveDate = Local::now().naive_local().date();
-    let overdue_tasks = filter_tasks::get_overdue_tasks(key).await;
-    println!("Found {} tasks to move to today", overdue_tasks.len());
-    for t in overdue_tasks.iter() {
-        // Update the date to today
-        // If it contains a time then need to preserve that
-        if t.due.date.contains("T") {
-            // Need to put the time on today
-            let due_date_time = parse_due_date_time(&t.due.date);
-            let today_with_time = today.and_time(due_date_time.time());
-            update_task::update_task_due(key, &t.id, today_with_time.format("%Y-%m-%dT%H:%M:%S").to_string(), t.due.lang.to_owned(), t.due.string.to_owned()).await;
-            println!("Rescheduled {content} to {due}", content = t.content, due = today_with_time)
-        }
-        // If it is only a date 
-        else {
-            update_task::update_task_due(key, &t.id, today.format("%Y-%m-%d").to_string(), t.due.lang.to_owned(), t.due.string.to_owned()).await;
-            println!("Rescheduled {content} to today", content = t.content)
-        }
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
     }
 }
@@ -0,0 +1,112 @@
+use api::filter_tasks::Task;
+use config::settings::{PostponeConfig, PostponeOrder};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::task_tree::TaskTree;
+
+// Sort the trees of tasks that can be postponed so the ones to move first come first, by their root task
+// The sort is stable so with no orderings, or a full tie, the API order is kept
+pub fn sort_trees(trees: &mut [TaskTree], config: &PostponeConfig, postpone_counts: &HashMap<String, i32>) {
+    trees.sort_by(|a, b| compare_tasks(a.root(), b.root(), config, postpone_counts));
+}
+
+fn compare_tasks(a: &Task, b: &Task, config: &PostponeConfig, postpone_counts: &HashMap<String, i32>) -> Ordering {
+    config.order.iter()
+        .map(|o| compare(a, b, *o, config, postpone_counts))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn compare(a: &Task, b: &Task, order: PostponeOrder, config: &PostponeConfig, postpone_counts: &HashMap<String, i32>) -> Ordering {
+    match order {
+        PostponeOrder::DayOrder => b.day_order.cmp(&a.day_order),
+        // Tasks without an added date are treated as the newest
+        PostponeOrder::Oldest => compare_added(a, b),
+        PostponeOrder::Newest => compare_added(b, a),
+        PostponeOrder::MostPostponed => postpone_counts.get(&b.id).unwrap_or(&0).cmp(postpone_counts.get(&a.id).unwrap_or(&0)),
+        PostponeOrder::Project => rank(&config.project_order, std::slice::from_ref(&a.project_id))
+            .cmp(&rank(&config.project_order, std::slice::from_ref(&b.project_id))),
+        PostponeOrder::Label => rank(&config.label_order, &a.labels).cmp(&rank(&config.label_order, &b.labels)),
+    }
+}
+
+fn compare_added(a: &Task, b: &Task) -> Ordering {
+    match (&a.added_at, &b.added_at) {
+        // ISO 8601 timestamps sort correctly as text
+        (Some(x), Some(y)) => x.cmp(y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// The position of the first listed value the task has, anything not listed goes last
+fn rank(listed: &[String], values: &[String]) -> usize {
+    listed.iter()
+        .position(|l| values.contains(l))
+        .unwrap_or(listed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_tree::build_trees;
+    use crate::test_support::{labels, task};
+
+    fn sorted(tasks: &[&Task], config: &PostponeConfig, counts: &HashMap<String, i32>) -> Vec<String> {
+        let mut trees = build_trees(tasks.iter().copied());
+        sort_trees(&mut trees, config, counts);
+        trees.iter().map(|tree| tree.root().id.clone()).collect()
+    }
+
+    fn config(order: Vec<PostponeOrder>) -> PostponeConfig {
+        PostponeConfig {
+            order,
+            project_order: vec!["someday".to_string()],
+            label_order: vec!["optional".to_string(), "nice".to_string()],
+            ..PostponeConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_no_order_keeps_api_order() {
+        let (a, b) = (Task { day_order: 1, ..task("a") }, Task { day_order: 2, ..task("b") });
+        let tasks = [&a, &b];
+        assert_eq!(sorted(&tasks, &config(Vec::new()), &HashMap::new()), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_day_order_moves_bottom_of_list_first() {
+        let (a, b) = (Task { day_order: 1, ..task("a") }, Task { day_order: 2, ..task("b") });
+        let tasks = [&a, &b];
+        assert_eq!(sorted(&tasks, &config(vec![PostponeOrder::DayOrder]), &HashMap::new()), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_age_orders() {
+        let a = Task { added_at: Some("2025-07-01T10:00:00Z".to_string()), ..task("a") };
+        let b = Task { added_at: Some("2025-06-01T10:00:00Z".to_string()), ..task("b") };
+        let c = task("c");
+        let tasks = [&a, &b, &c];
+        assert_eq!(sorted(&tasks, &config(vec![PostponeOrder::Oldest]), &HashMap::new()), vec!["b", "a", "c"]);
+        assert_eq!(sorted(&tasks, &config(vec![PostponeOrder::Newest]), &HashMap::new()), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_most_postponed_first() {
+        let (a, b) = (task("a"), task("b"));
+        let tasks = [&a, &b];
+        let counts = HashMap::from([("b".to_string(), 3)]);
+        assert_eq!(sorted(&tasks, &config(vec![PostponeOrder::MostPostponed]), &counts), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_project_and_label_with_tie_break() {
+        let a = Task { project_id: "work".to_string(), labels: labels(&["nice"]), ..task("a") };
+        let b = Task { project_id: "someday".to_string(), ..task("b") };
+        let c = Task { project_id: "work".to_string(), labels: labels(&["optional"]), ..task("c") };
+        let tasks = [&a, &b, &c];
+        assert_eq!(sorted(&tasks, &config(vec![PostponeOrder::Project, PostponeOrder::Label]), &HashMap::new()), vec!["b", "c", "a"]);
+    }
+}
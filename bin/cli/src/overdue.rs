@@ -0,0 +1,123 @@
+use api::filter_tasks::{self, Task};
+use api::update_task::{self, TaskUpdate};
+use chrono::{Datelike, Days, NaiveDate};
+use config::settings::{Config, DeadlinePolicy, OverdueConfig, OverduePolicy};
+
+use crate::deadlines;
+use crate::load_balance;
+use crate::reschedule;
+use crate::working_days::WorkingDays;
+
+// Bring overdue tasks back into the schedule using the configured policy
+pub async fn overdue(key: &str, config: &Config, today: NaiveDate) {
+    let overdue_tasks = filter_tasks::get_overdue_tasks(key, &config.api, today).await;
+    match config.overdue.policy {
+        OverduePolicy::Today => {
+            println!("Found {} tasks to move to today", overdue_tasks.len());
+            for t in overdue_tasks.iter() {
+                reschedule::move_task_to_date(key, config, t, today, config.recurring.overdue).await;
+            }
+        },
+        OverduePolicy::Spread => {
+            println!("Found {} overdue tasks to spread from today", overdue_tasks.len());
+            let working_days = WorkingDays::load(config);
+            let mut days: Vec<NaiveDate> = vec![today];
+            days.extend((1..config.overdue.spread_days).map(|i| working_days.add_working_days(today, i)));
+            let mut loads = load_balance::loads_on(key, config, &days).await;
+            let assigned_days = load_balance::assign(&mut loads, overdue_tasks.len(), config.overdue.max_tasks_per_day);
+            for (t, day) in overdue_tasks.iter().zip(assigned_days) {
+                move_forward(key, config, t, day, today).await;
+            }
+        },
+        OverduePolicy::SameWeekday => {
+            println!("Found {} overdue tasks to move to the same weekday", overdue_tasks.len());
+            for t in overdue_tasks.iter() {
+                move_forward(key, config, t, same_weekday_after(t.due.date.date(), today), today).await;
+            }
+        },
+        OverduePolicy::Triage => {
+            // Tasks already waiting for triage are left alone
+            let to_handle: Vec<&Task> = overdue_tasks.iter()
+                .filter(|t| !t.labels.contains(&config.overdue.triage_label))
+                .collect();
+            println!("Found {} overdue tasks to move to today or triage", to_handle.len());
+            for t in to_handle.iter() {
+                if needs_triage(t, today, &config.overdue) {
+                    label_for_triage(key, config, t).await;
+                }
+                else {
+                    reschedule::move_task_to_date(key, config, t, today, config.recurring.overdue).await;
+                }
+            }
+        },
+    }
+}
+
+// Move an overdue task to a day after today without passing its deadline, bringing it to today when it can't go later
+async fn move_forward(key: &str, config: &Config, t: &Task, day: NaiveDate, today: NaiveDate) {
+    let limited = within_deadline(t, day, today, config.postpone.deadline);
+    if limited != day {
+        println!("Moving {content} to {limited} rather than {day} so it doesn't pass its deadline", content = t.content,
+            limited = limited.format(&config.formats.date), day = day.format(&config.formats.date));
+    }
+    reschedule::move_task_to_date(key, config, t, limited, config.recurring.overdue).await;
+}
+
+fn within_deadline(t: &Task, day: NaiveDate, today: NaiveDate, policy: DeadlinePolicy) -> NaiveDate {
+    deadlines::limit_to_deadline(t, day, policy).unwrap_or(today).max(today)
+}
+
+// The first day after today that falls on the same weekday as the day given
+fn same_weekday_after(day: NaiveDate, today: NaiveDate) -> NaiveDate {
+    let ahead = (7 + day.weekday().num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 { 7 } else { ahead };
+    today.checked_add_days(Days::new(ahead as u64)).unwrap()
+}
+
+fn needs_triage(t: &Task, today: NaiveDate, config: &OverdueConfig) -> bool {
+    (today - t.due.date.date()).num_days() >= config.triage_after_days
+}
+
+async fn label_for_triage(key: &str, config: &Config, t: &Task) {
+    let labels: Vec<String> = t.labels.iter().cloned().chain([config.overdue.triage_label.to_owned()]).collect();
+    update_task::update_task(key, &config.api, &t.id, &TaskUpdate { labels: Some(labels), ..TaskUpdate::default() }).await;
+    println!("Labelled {content} with {label} for triage", content = t.content, label = config.overdue.triage_label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{deadline, due, task};
+    use api::filter_tasks::DueDate;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_same_weekday_is_always_after_today() {
+        // Monday the 4th
+        assert_eq!(same_weekday_after(day(1), day(4)), day(8));
+        assert_eq!(same_weekday_after(day(3), day(4)), day(10));
+        // Due on a Monday a week ago
+        assert_eq!(same_weekday_after(NaiveDate::from_ymd_opt(2025, 7, 28).unwrap(), day(4)), day(11));
+    }
+
+    #[test]
+    fn test_moves_forward_stop_at_the_deadline() {
+        let overdue = Task { due: due(DueDate::Date(day(1))), ..task("1") };
+        let with_deadline = |date: &str| Task { deadline: deadline(date), ..task("1") };
+        assert_eq!(within_deadline(&with_deadline("2025-08-06"), day(8), day(4), DeadlinePolicy::Cap), day(6));
+        assert_eq!(within_deadline(&with_deadline("2025-08-06"), day(8), day(4), DeadlinePolicy::Refuse), day(4));
+        // A deadline that has already passed brings the task to today
+        assert_eq!(within_deadline(&with_deadline("2025-08-02"), day(8), day(4), DeadlinePolicy::Cap), day(4));
+        assert_eq!(within_deadline(&overdue, day(8), day(4), DeadlinePolicy::Cap), day(8));
+    }
+
+    #[test]
+    fn test_triage_after_days() {
+        let config = OverdueConfig { triage_after_days: 3, ..OverdueConfig::default() };
+        assert!(!needs_triage(&Task { due: due(DueDate::Date(day(2))), ..task("1") }, day(4), &config));
+        assert!(needs_triage(&Task { due: due(DueDate::Date(day(1))), ..task("1") }, day(4), &config));
+    }
+}
@@ -0,0 +1,164 @@
+use api::filter_tasks::{self, Task};
+use chrono::NaiveDate;
+use config::settings::Config;
+use db::postpone_counts;
+use std::collections::HashMap;
+
+use crate::ordering;
+use crate::protection::Protection;
+use crate::reschedule;
+use crate::task_tree::{self, TaskTree};
+use crate::working_days::WorkingDays;
+
+// A day with the number of tasks at each priority and the trees that could be moved off it
+struct Day<'a> {
+    day: NaiveDate,
+    counts: HashMap<i32, i32>,
+    movable: Vec<TaskTree<'a>>,
+}
+
+// A day left over a limit as none of its tasks at that priority could be moved
+#[derive(Debug, PartialEq)]
+struct Conflict {
+    day: NaiveDate,
+    priority: i32,
+    count: i32,
+}
+
+// Move high priority tasks off days over their limit onto the next day with room, reporting any that can't be resolved
+pub async fn balance_priorities(key: &str, config: &Config, today: NaiveDate) {
+    let limits = config.priorities.limits();
+    if limits.is_empty() {
+        println!("No priority limits are set, see the priorities config");
+        return;
+    }
+    let working_days = WorkingDays::load(config);
+    let mut dates: Vec<NaiveDate> = vec![today];
+    dates.extend((1..config.priorities.days).map(|i| working_days.add_working_days(today, i)));
+    let mut tasks: Vec<Vec<Task>> = Vec::new();
+    for day in dates.iter() {
+        tasks.push(filter_tasks::get_tasks_due_on(key, &config.api, *day).await);
+    }
+
+    let protection = Protection::new(&config.protect);
+    let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+    let mut days: Vec<Day> = Vec::new();
+    for (day, day_tasks) in dates.iter().zip(tasks.iter()) {
+        // Tasks at a set time, with a deadline or protected stay on their day
+        let candidates = protection.unprotected(day_tasks.iter()
+            .filter(|t| !t.due.date.has_time())
+            .filter(|t| t.deadline.is_none())
+            .filter(|t| !reschedule::is_left_in_place(t, config.recurring.balance_priorities)));
+        let mut movable = task_tree::movable_trees(task_tree::build_trees(day_tasks.iter()), &candidates);
+        // The trees the configured order would postpone first are the lowest ranked so move first
+        ordering::sort_trees(&mut movable, &config.postpone, &counts);
+        days.push(Day { day: *day, counts: priority_counts(day_tasks.iter()), movable });
+    }
+
+    let (moves, conflicts) = plan_moves(&mut days, &limits);
+    if moves.is_empty() && conflicts.is_empty() {
+        println!("Every day is within the priority limits");
+    }
+    for (tree, day) in moves.iter() {
+        for t in tree.tasks.iter() {
+            reschedule::postpone_task_to_date(key, config, t, *day, config.recurring.balance_priorities).await;
+        }
+    }
+    for c in conflicts.iter() {
+        let max = limits.iter().find(|(p, _)| *p == c.priority).map(|(_, m)| *m).unwrap_or(0);
+        println!("{day} still has {count} p{name} tasks, over the limit of {max}, as none of them can be moved to a day with room",
+            day = c.day.format(&config.formats.date), count = c.count, name = 5 - c.priority);
+    }
+}
+
+fn priority_counts<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> HashMap<i32, i32> {
+    let mut counts: HashMap<i32, i32> = HashMap::new();
+    for t in tasks.into_iter() {
+        *counts.entry(t.priority).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn count(counts: &HashMap<i32, i32>, priority: i32) -> i32 {
+    counts.get(&priority).copied().unwrap_or(0)
+}
+
+// Whether the tree can be added to the day without going over any limit
+fn fits(day: &HashMap<i32, i32>, tree: &HashMap<i32, i32>, limits: &[(i32, i32)]) -> bool {
+    limits.iter().all(|(p, max)| count(tree, *p) == 0 || count(day, *p) + count(tree, *p) <= *max)
+}
+
+// Go through the days in order, moving the lowest ranked trees at each priority over its limit to the next day with room
+fn plan_moves<'a>(days: &mut [Day<'a>], limits: &[(i32, i32)]) -> (Vec<(TaskTree<'a>, NaiveDate)>, Vec<Conflict>) {
+    let mut moves: Vec<(TaskTree, NaiveDate)> = Vec::new();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for from in 0..days.len() {
+        for (priority, max) in limits.iter() {
+            let mut i = 0;
+            while count(&days[from].counts, *priority) > *max && i < days[from].movable.len() {
+                let tree_counts = priority_counts(days[from].movable[i].tasks.iter().copied());
+                if count(&tree_counts, *priority) == 0 {
+                    i += 1;
+                    continue;
+                }
+                match (from + 1..days.len()).find(|to| fits(&days[*to].counts, &tree_counts, limits)) {
+                    Some(to) => {
+                        let tree = days[from].movable.remove(i);
+                        for (p, c) in tree_counts.iter() {
+                            *days[from].counts.entry(*p).or_insert(0) -= c;
+                            *days[to].counts.entry(*p).or_insert(0) += c;
+                        }
+                        moves.push((tree, days[to].day));
+                    },
+                    None => i += 1,
+                }
+            }
+            let left = count(&days[from].counts, *priority);
+            if left > *max {
+                conflicts.push(Conflict { day: days[from].day, priority: *priority, count: left });
+            }
+        }
+    }
+    (moves, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::task;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    fn moved(moves: &[(TaskTree, NaiveDate)]) -> Vec<(String, NaiveDate)> {
+        moves.iter().map(|(tree, d)| (tree.root().id.clone(), *d)).collect()
+    }
+
+    #[test]
+    fn test_lowest_ranked_move_to_the_next_day_with_room() {
+        let first = [Task { priority: 4, ..task("a") }, Task { priority: 4, ..task("b") }, Task { priority: 4, ..task("c") }, task("d")];
+        let second = [Task { priority: 4, ..task("e") }, Task { priority: 4, ..task("f") }];
+        let mut days = vec![
+            Day { day: day(4), counts: priority_counts(first.iter()), movable: task_tree::build_trees(first.iter()) },
+            Day { day: day(5), counts: priority_counts(second.iter()), movable: Vec::new() },
+            Day { day: day(6), counts: HashMap::new(), movable: Vec::new() },
+        ];
+        let (moves, conflicts) = plan_moves(&mut days, &[(4, 2)]);
+        // The 5th is already at the limit so a goes on to the 6th
+        assert_eq!(moved(&moves), vec![("a".to_string(), day(6))]);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_are_reported_when_nothing_can_move() {
+        let first = [Task { priority: 4, ..task("a") }, Task { priority: 4, ..task("b") }];
+        let mut days = vec![
+            Day { day: day(4), counts: priority_counts(first.iter()), movable: Vec::new() },
+            Day { day: day(5), counts: HashMap::new(), movable: Vec::new() },
+        ];
+        let (moves, conflicts) = plan_moves(&mut days, &[(4, 1)]);
+        assert!(moves.is_empty());
+        assert_eq!(conflicts, vec![Conflict { day: day(4), priority: 4, count: 2 }]);
+    }
+}
@@ -0,0 +1,162 @@
+use api::filter_tasks::{Deadline, Task, UndatedTask};
+use config::settings::ProtectConfig;
+use regex::Regex;
+
+// The parts of a task the rules look at, so dated and undated tasks are protected the same way
+pub trait Protectable {
+    fn content(&self) -> &str;
+    fn labels(&self) -> &[String];
+    fn project_id(&self) -> &str;
+    fn section_id(&self) -> Option<&String>;
+    fn deadline(&self) -> Option<&Deadline>;
+}
+
+impl Protectable for Task {
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    fn section_id(&self) -> Option<&String> {
+        self.section_id.as_ref()
+    }
+
+    fn deadline(&self) -> Option<&Deadline> {
+        self.deadline.as_ref()
+    }
+}
+
+impl Protectable for UndatedTask {
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    fn section_id(&self) -> Option<&String> {
+        self.section_id.as_ref()
+    }
+
+    fn deadline(&self) -> Option<&Deadline> {
+        self.deadline.as_ref()
+    }
+}
+
+// The rules that keep a task where it is no matter how it would otherwise be postponed
+pub struct Protection {
+    config: ProtectConfig,
+    patterns: Vec<Regex>,
+}
+
+impl Protection {
+    pub fn new(config: &ProtectConfig) -> Protection {
+        Protection {
+            config: config.clone(),
+            // The patterns are checked when the config is validated
+            patterns: config.content_patterns.iter()
+                .map(|p| Regex::new(p).expect("Content patterns should be validated"))
+                .collect(),
+        }
+    }
+
+    // Every rule the task matches, empty if it can be postponed
+    pub fn reasons(&self, t: &impl Protectable) -> Vec<String> {
+        let mut reasons: Vec<String> = Vec::new();
+        for l in t.labels().iter().filter(|l| self.config.labels.contains(l)) {
+            reasons.push(format!("has label {l}"));
+        }
+        if self.config.projects.iter().any(|p| p == t.project_id()) {
+            reasons.push(format!("in project {p}", p = t.project_id()));
+        }
+        if let Some(s) = t.section_id().filter(|s| self.config.sections.contains(s)) {
+            reasons.push(format!("in section {s}"));
+        }
+        if self.config.deadline {
+            if let Some(d) = t.deadline() {
+                reasons.push(format!("has deadline {d}", d = d.date));
+            }
+        }
+        for p in self.patterns.iter().filter(|p| p.is_match(t.content())) {
+            reasons.push(format!("matches {p}"));
+        }
+        reasons
+    }
+
+    // The protected tasks along with why each one is protected
+    pub fn protected<'a, T: Protectable>(&self, tasks: impl IntoIterator<Item = &'a T>) -> Vec<(&'a T, String)> {
+        tasks.into_iter()
+            .map(|t| (t, self.reasons(t)))
+            .filter(|(_, r)| !r.is_empty())
+            .map(|(t, r)| (t, r.join(", ")))
+            .collect()
+    }
+
+    // The tasks that can be postponed, printing the ones left behind and why
+    pub fn unprotected<'a, T: Protectable>(&self, tasks: impl IntoIterator<Item = &'a T>) -> Vec<&'a T> {
+        tasks.into_iter()
+            .filter(|t| {
+                let reasons = self.reasons(*t);
+                if !reasons.is_empty() {
+                    println!("Protected {content}: {reasons}", content = t.content(), reasons = reasons.join(", "));
+                }
+                reasons.is_empty()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{deadline, labels, task};
+
+    fn protection() -> Protection {
+        Protection::new(&ProtectConfig {
+            labels: vec!["fixed".to_string()],
+            projects: vec!["appointments".to_string()],
+            sections: Vec::new(),
+            deadline: true,
+            content_patterns: vec!["(?i)^call ".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_unmatched_task_is_not_protected() {
+        let t = Task { labels: labels(&["home"]), ..task("Tidy desk") };
+        assert!(protection().reasons(&t).is_empty());
+    }
+
+    #[test]
+    fn test_each_rule_gives_a_reason() {
+        let p = protection();
+        assert_eq!(p.reasons(&Task { labels: labels(&["fixed"]), ..task("Tidy desk") }), vec!["has label fixed"]);
+        assert_eq!(p.reasons(&Task { project_id: "appointments".to_string(), ..task("Tidy desk") }), vec!["in project appointments"]);
+        assert_eq!(p.reasons(&Task { deadline: deadline("2025-08-08"), ..task("Tidy desk") }), vec!["has deadline 2025-08-08"]);
+        assert_eq!(p.reasons(&task("Call the bank")), vec!["matches (?i)^call "]);
+    }
+
+    #[test]
+    fn test_unprotected_keeps_order() {
+        let a = task("Tidy desk");
+        let b = task("Call the bank");
+        let c = task("Water plants");
+        let tasks = [a, b, c];
+        let p = protection();
+        let left: Vec<&str> = p.unprotected(tasks.iter()).iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(left, vec!["Tidy desk", "Water plants"]);
+        assert_eq!(p.protected(tasks.iter()).len(), 1);
+    }
+}
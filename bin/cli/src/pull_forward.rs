@@ -0,0 +1,108 @@
+use api::filter_tasks::{self, Task};
+use api::completed_fetch;
+use chrono::NaiveDate;
+use config::settings::{Config, RecurringPolicy};
+
+use crate::busy_days::{self, BusyDays};
+use crate::floating_week;
+use crate::protection::Protection;
+use crate::reschedule;
+use crate::task_tree::{self, TaskTree};
+use crate::working_days::WorkingDays;
+
+// Pull tasks from the upcoming working days into today until it has enough to meet the weekly goal
+pub async fn pull_forward(key: &str, config: &Config, today: NaiveDate) {
+    let todays_tasks = filter_tasks::get_todays_tasks(key, &config.api, today).await;
+    let count_once = config.tasks.count_tree_once;
+    let total_today_tasks: i32 = task_tree::build_trees(todays_tasks.iter()).iter().map(|tree| tree.size(count_once)).sum();
+    println!("Found {} tasks for today", total_today_tasks);
+    let stats = completed_fetch::get_completed_stats(key, &config.api).await;
+    let busy_days = BusyDays::load(config, today).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+    let excluded_days = busy_days::excluded_days(&busy_days, config, today);
+    let week_progress = floating_week::calculate_progress_on_floating_week(&stats, &excluded_days);
+    let daily_cap = busy_days.scale(config.daily_cap(stats.goals.weekly_goal), today);
+    let needed = week_progress.remaining(daily_cap) - total_today_tasks;
+    if needed <= 0 {
+        println!("Today already has enough tasks to meet the weekly goal");
+        return;
+    }
+
+    let working_days = WorkingDays::load(config);
+    let mut upcoming: Vec<Task> = Vec::new();
+    for i in 1..=config.postpone.horizon_days {
+        let day = working_days.add_working_days(today, i);
+        upcoming.extend(filter_tasks::get_tasks_due_on(key, &config.api, day).await);
+    }
+    let candidates = Protection::new(&config.protect).unprotected(upcoming.iter().filter(|t| is_pullable(t)));
+    // Subtasks move with their parent so only whole trees can be moved
+    let trees = task_tree::movable_trees(task_tree::build_trees(upcoming.iter()), &candidates);
+    let to_pull = select(trees, needed, count_once);
+    println!("Pulling {pulled} of the {needed} tasks needed into today", pulled = to_pull.iter().map(|tree| tree.size(count_once)).sum::<i32>());
+    for t in to_pull.iter().flat_map(|tree| tree.tasks.iter()) {
+        reschedule::move_task_to_date(key, config, t, today, RecurringPolicy::Occurrence).await;
+    }
+}
+
+// Recurring tasks would be pulled off their rhythm and tasks with a duration are blocked out at a set time
+fn is_pullable(t: &Task) -> bool {
+    !t.due.is_recurring && t.duration.is_none()
+}
+
+// Trees with the nearest deadline come first, otherwise the soonest due, which is the order they were fetched
+// Whole trees are taken while they fit in the number needed
+fn select(mut trees: Vec<TaskTree>, needed: i32, count_once: bool) -> Vec<TaskTree> {
+    trees.sort_by_key(|tree| tree.tasks.iter()
+        .filter_map(|t| t.deadline.as_ref().and_then(|d| d.date()))
+        .min()
+        .unwrap_or(NaiveDate::MAX));
+    let mut selected: Vec<TaskTree> = Vec::new();
+    let mut taken = 0;
+    for tree in trees.into_iter() {
+        if taken + tree.size(count_once) <= needed {
+            taken += tree.size(count_once);
+            selected.push(tree);
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{deadline, task};
+    use api::filter_tasks::Due;
+
+    #[test]
+    fn test_recurring_tasks_stay() {
+        assert!(is_pullable(&task("a")));
+        assert!(!is_pullable(&Task { due: Due { is_recurring: true, ..task("a").due }, ..task("a") }));
+    }
+
+    #[test]
+    fn test_nearest_deadline_first_then_fetch_order() {
+        let tasks = [
+            task("a"),
+            Task { deadline: deadline("2025-08-10"), ..task("b") },
+            task("c"),
+            Task { deadline: deadline("2025-08-07"), ..task("d") },
+        ];
+        let selected = select(task_tree::build_trees(tasks.iter()), 3, false);
+        let ids: Vec<&str> = selected.iter().map(|tree| tree.root().id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "b", "a"]);
+    }
+
+    #[test]
+    fn test_whole_trees_are_taken_while_they_fit() {
+        let tasks = [
+            task("parent"),
+            Task { parent_id: Some("parent".to_string()), deadline: deadline("2025-08-07"), ..task("child") },
+            task("other"),
+        ];
+        let ids = |selected: Vec<TaskTree>| -> Vec<String> { selected.iter().map(|tree| tree.root().id.clone()).collect() };
+        assert_eq!(ids(select(task_tree::build_trees(tasks.iter()), 1, false)), vec!["other"]);
+        assert_eq!(ids(select(task_tree::build_trees(tasks.iter()), 1, true)), vec!["parent"]);
+    }
+}
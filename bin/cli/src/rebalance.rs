@@ -0,0 +1,148 @@
+use api::completed_fetch;
+use api::filter_tasks::{self, Task};
+use chrono::{Days, NaiveDate};
+use config::settings::{Config, RebalanceBy};
+use db::postpone_counts;
+
+use crate::busy_days::BusyDays;
+use crate::capacity;
+use crate::ordering;
+use crate::protection::Protection;
+use crate::reschedule;
+use crate::task_tree::{self, TaskTree};
+use crate::working_days::WorkingDays;
+
+// An upcoming day with the trees that could be moved off it
+struct Day<'a> {
+    day: NaiveDate,
+    target: i32,
+    load: i32,
+    movable: Vec<TaskTree<'a>>,
+}
+
+// Even out the upcoming working days toward their targets, moving tasks off days that are over onto days with room
+pub async fn rebalance(key: &str, config: &Config, today: NaiveDate) {
+    let working_days = WorkingDays::load(config);
+    let days: Vec<NaiveDate> = (1..=config.rebalance.days)
+        .map(|i| today.checked_add_days(Days::new(i)).unwrap())
+        .filter(|d| working_days.is_working_day(*d))
+        .collect();
+    let mut tasks: Vec<Vec<Task>> = Vec::new();
+    for day in days.iter() {
+        tasks.push(filter_tasks::get_tasks_due_on(key, &config.api, *day).await);
+    }
+
+    let daily_cap = match config.rebalance.by {
+        RebalanceBy::Count => config.daily_cap(completed_fetch::get_completed_stats(key, &config.api).await.goals.weekly_goal),
+        RebalanceBy::Minutes => 0,
+    };
+    let busy_days = BusyDays::load(config, today).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+    let target = |day: NaiveDate| match config.rebalance.by {
+        RebalanceBy::Count => busy_days.scale(daily_cap, day),
+        RebalanceBy::Minutes => config.capacity.minutes_on(day),
+    };
+    let size = |tree: &TaskTree| match config.rebalance.by {
+        RebalanceBy::Count => tree.size(config.tasks.count_tree_once),
+        RebalanceBy::Minutes => tree.tasks.iter().map(|t| capacity::task_minutes(t, &config.capacity)).sum(),
+    };
+
+    let protection = Protection::new(&config.protect);
+    let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+    let mut loads: Vec<Day> = Vec::new();
+    for (day, day_tasks) in days.iter().zip(tasks.iter()) {
+        let trees = task_tree::build_trees(day_tasks.iter());
+        let load = trees.iter().map(&size).sum();
+        // Tasks at a set time, with a deadline or protected stay on their day
+        let candidates = protection.unprotected(day_tasks.iter()
+            .filter(|t| !t.due.date.has_time())
+            .filter(|t| t.deadline.is_none())
+            .filter(|t| !reschedule::is_left_in_place(t, config.recurring.rebalance)));
+        let mut movable = task_tree::movable_trees(trees, &candidates);
+        ordering::sort_trees(&mut movable, &config.postpone, &counts);
+        loads.push(Day { day: *day, target: target(*day), load, movable });
+    }
+
+    let moves = plan_moves(&mut loads, size);
+    if moves.is_empty() {
+        println!("The upcoming days are already balanced");
+    }
+    for (tree, day) in moves.iter() {
+        for t in tree.tasks.iter() {
+            reschedule::move_task_to_date(key, config, t, *day, config.recurring.rebalance).await;
+        }
+    }
+    for d in loads.iter() {
+        println!("{day}: {load} of {target}", day = d.day.format(&config.formats.date), load = d.load, target = d.target);
+    }
+}
+
+// Move trees off the days most over their target onto the days with the most room, never filling a day past its target
+fn plan_moves<'a>(days: &mut [Day<'a>], size: impl Fn(&TaskTree) -> i32) -> Vec<(TaskTree<'a>, NaiveDate)> {
+    let mut moves: Vec<(TaskTree, NaiveDate)> = Vec::new();
+    let mut over: Vec<usize> = (0..days.len()).filter(|i| days[*i].load > days[*i].target).collect();
+    over.sort_by_key(|i| (days[*i].target - days[*i].load, days[*i].day));
+    for from in over.into_iter() {
+        let movable = std::mem::take(&mut days[from].movable);
+        for tree in movable.into_iter() {
+            let length = size(&tree);
+            if days[from].load <= days[from].target || length <= 0 {
+                days[from].movable.push(tree);
+                continue;
+            }
+            let to = (0..days.len())
+                .filter(|i| days[*i].load + length <= days[*i].target)
+                .max_by_key(|i| (days[*i].target - days[*i].load, std::cmp::Reverse(days[*i].day)));
+            match to {
+                Some(to) => {
+                    days[from].load -= length;
+                    days[to].load += length;
+                    moves.push((tree, days[to].day));
+                },
+                None => days[from].movable.push(tree),
+            }
+        }
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::task;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    fn moved(moves: &[(TaskTree, NaiveDate)]) -> Vec<(String, NaiveDate)> {
+        moves.iter().map(|(tree, d)| (tree.root().id.clone(), *d)).collect()
+    }
+
+    #[test]
+    fn test_overloaded_day_moves_to_days_with_the_most_room() {
+        let tasks = [task("a"), task("b"), task("c")];
+        let mut days = vec![
+            Day { day: day(4), target: 2, load: 5, movable: task_tree::build_trees(tasks.iter()) },
+            Day { day: day(5), target: 2, load: 1, movable: Vec::new() },
+            Day { day: day(6), target: 2, load: 0, movable: Vec::new() },
+        ];
+        let moves = plan_moves(&mut days, |tree| tree.size(false));
+        assert_eq!(moved(&moves), vec![("a".to_string(), day(6)), ("b".to_string(), day(5)), ("c".to_string(), day(6))]);
+        assert_eq!(days.iter().map(|d| d.load).collect::<Vec<i32>>(), vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn test_days_are_never_filled_past_their_target() {
+        let tasks = [task("a"), task("b")];
+        let mut days = vec![
+            Day { day: day(4), target: 0, load: 2, movable: task_tree::build_trees(tasks.iter()) },
+            Day { day: day(5), target: 1, load: 0, movable: Vec::new() },
+        ];
+        let moves = plan_moves(&mut days, |tree| tree.size(false));
+        assert_eq!(moved(&moves), vec![("a".to_string(), day(5))]);
+        assert_eq!(days[0].movable.len(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+// How often an English Todoist recurring due string repeats
+#[derive(Debug, PartialEq)]
+enum Every {
+    Days(u32),
+    // Empty days repeat on the weekday of the current due date
+    Weeks(u32, Vec<Weekday>),
+    Months(u32),
+}
+
+// The first occurrence after the day given, counted on from the current due date so interval rules keep their anchor
+// None when the rule isn't one that can be worked out here, such as other languages or a day of the month
+pub fn next_occurrence(due_string: &str, due: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+    let every = parse(due_string)?;
+    match every {
+        Every::Days(n) => {
+            let mut day = due;
+            while day <= after {
+                day = day.checked_add_days(Days::new(n as u64))?;
+            }
+            Some(day)
+        },
+        Every::Weeks(n, days) => {
+            let days = if days.is_empty() { vec![due.weekday()] } else { days };
+            let first_week = due.week(Weekday::Mon).first_day();
+            // Within twice the interval there is always a matching day
+            (1..=14 * n as u64)
+                .filter_map(|i| after.checked_add_days(Days::new(i)))
+                .find(|d| days.contains(&d.weekday()) && ((d.week(Weekday::Mon).first_day() - first_week).num_weeks() as u32).is_multiple_of(n))
+        },
+        Every::Months(n) => (1..)
+            .map(|k| due.checked_add_months(Months::new(n * k)))
+            .find(|d| d.is_none_or(|d| d > after))?,
+    }
+}
+
+fn parse(due_string: &str) -> Option<Every> {
+    let lower = due_string.to_lowercase();
+    // The start, end and time don't change which days the rule falls on
+    let rule = [" starting ", " from ", " until ", " ending ", " for ", " at "].iter()
+        .fold(lower.as_str(), |rule, clause| rule.split(clause).next().unwrap_or(rule));
+    let words: Vec<&str> = rule.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty() && *w != "and" && !is_time(w))
+        .collect();
+    match words.as_slice() {
+        ["daily"] => return Some(Every::Days(1)),
+        ["weekly"] => return Some(Every::Weeks(1, Vec::new())),
+        ["monthly"] => return Some(Every::Months(1)),
+        ["yearly"] | ["annually"] => return Some(Every::Months(12)),
+        _ => {},
+    }
+    let (first, rest) = words.split_first()?;
+    if *first != "every" && *first != "every!" {
+        return None;
+    }
+    let (n, rest) = match rest {
+        ["other", rest @ ..] => (2, rest),
+        [n, rest @ ..] if n.parse::<u32>().is_ok_and(|n| n > 0) => (n.parse().unwrap(), rest),
+        _ => (1, rest),
+    };
+    match rest {
+        ["day" | "days"] => Some(Every::Days(n)),
+        ["week" | "weeks"] => Some(Every::Weeks(n, Vec::new())),
+        ["month" | "months"] => Some(Every::Months(n)),
+        ["year" | "years"] => Some(Every::Months(12 * n)),
+        ["weekday" | "workday"] => Some(Every::Weeks(n, vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri])),
+        ["weekend"] => Some(Every::Weeks(n, vec![Weekday::Sat, Weekday::Sun])),
+        [] => None,
+        days => days.iter().map(|d| weekday(d)).collect::<Option<Vec<Weekday>>>().map(|days| Every::Weeks(n, days)),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// A time of day such as 9am or 14:30, which doesn't change the day
+fn is_time(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit()) && (word.ends_with("am") || word.ends_with("pm") || word.contains(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_interval_rules_keep_their_anchor() {
+        // Due on the 1st every 3 days, so the 4th and the 7th, not 3 days on from today
+        assert_eq!(next_occurrence("every 3 days", day(1), day(4)), Some(day(7)));
+        // Monday the 4th every other week
+        assert_eq!(next_occurrence("every 2 weeks", day(4), day(11)), Some(day(18)));
+        assert_eq!(next_occurrence("every other monday at 9am", day(4), day(4)), Some(day(18)));
+        assert_eq!(next_occurrence("every day starting Jul 1 2025", day(4), day(4)), Some(day(5)));
+    }
+
+    #[test]
+    fn test_weekdays_and_months() {
+        // Friday the 8th
+        assert_eq!(next_occurrence("every weekday", day(8), day(8)), Some(day(11)));
+        assert_eq!(next_occurrence("every mon, wed", day(4), day(4)), Some(day(6)));
+        assert_eq!(next_occurrence("every month", NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(), day(4)), Some(day(31)));
+        assert_eq!(next_occurrence("yearly", day(4), day(4)), NaiveDate::from_ymd_opt(2026, 8, 4));
+    }
+
+    #[test]
+    fn test_rules_that_cant_be_worked_out() {
+        assert_eq!(next_occurrence("every 1st", day(1), day(4)), None);
+        assert_eq!(next_occurrence("every 3rd friday", day(1), day(4)), None);
+        assert_eq!(next_occurrence("jeden Tag", day(1), day(4)), None);
+    }
+}
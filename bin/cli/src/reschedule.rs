@@ -0,0 +1,101 @@
+use api::filter_tasks::{self, DueDate};
+use api::update_task::{self, DueUpdate};
+use chrono::NaiveDate;
+use config::settings::{Config, RecurringPolicy};
+use db::postpone_counts;
+
+use crate::deadlines;
+use crate::escalation;
+use crate::recurring;
+use crate::working_days::WorkingDays;
+
+// Whether the policy means the task stays where it is, so shouldn't be counted as moved
+pub fn is_left_in_place(t: &filter_tasks::Task, policy: RecurringPolicy) -> bool {
+    t.due.is_recurring && policy == RecurringPolicy::Leave
+}
+
+pub async fn postpone_task_to_tomorrow(key: &str, config: &Config, working_days: &WorkingDays, t: &filter_tasks::Task, policy: RecurringPolicy) {
+    postpone_task_by_days(key, config, working_days, t, 1, policy).await;
+}
+
+pub async fn postpone_task_by_days(key: &str, config: &Config, working_days: &WorkingDays, t: &filter_tasks::Task, days: i8, policy: RecurringPolicy) {
+    // Only working days are counted
+    let new_date = working_days.add_working_days(t.due.date.date(), days as u64);
+    postpone_task_to_date(key, config, t, new_date, policy).await;
+}
+
+pub async fn postpone_task_to_date(key: &str, config: &Config, t: &filter_tasks::Task, date: NaiveDate, policy: RecurringPolicy) {
+    // A task is never pushed past its deadline
+    let Some(date) = deadlines::limit_to_deadline(t, date, config.postpone.deadline) else {
+        println!("Left {content} in place as moving it would pass its deadline", content = t.content);
+        return;
+    };
+    // Any time is kept at the same wall clock time in the task's own timezone
+    let new_due = t.due.date.on_date(date);
+    if update_due(key, config, t, new_due, policy).await {
+        // Count the postponement so repeatedly postponed tasks can be ordered first and escalated
+        let count = postpone_counts::increment(&t.id, &t.content, config.day.today(), &config.db).expect("Failed to record postponement");
+        escalation::escalate(key, config, t, count).await;
+    }
+}
+
+// Move a task without counting it as a postponement, such as bringing it forward or back from overdue
+pub async fn move_task_to_date(key: &str, config: &Config, t: &filter_tasks::Task, date: NaiveDate, policy: RecurringPolicy) {
+    // Any time is kept at the same wall clock time in the task's own timezone
+    update_due(key, config, t, t.due.date.on_date(date), policy).await;
+}
+
+// Set the due date as given, such as giving a task a time on the same day
+pub async fn set_due(key: &str, config: &Config, t: &filter_tasks::Task, new_due: DueDate) {
+    update_due(key, config, t, new_due, RecurringPolicy::Occurrence).await;
+}
+
+// Send the new due date, recurring tasks are handled by the policy so their rule isn't re-anchored
+// Returns whether the task was moved
+async fn update_due(key: &str, config: &Config, t: &filter_tasks::Task, new_due: DueDate, policy: RecurringPolicy) -> bool {
+    let due_update = |string: Option<String>| DueUpdate {
+        date: Some(new_due.to_api_string()),
+        timezone: new_due.timezone(),
+        string,
+        lang: t.due.lang.to_owned(),
+        is_recurring: t.due.is_recurring,
+    };
+    match (t.due.is_recurring, t.due.string.to_owned()) {
+        (true, Some(due_string)) => match policy {
+            RecurringPolicy::Occurrence => {
+                update_task::update_task_due(key, &config.api, &t.id, due_update(Some(due_string))).await;
+                println!("Rescheduled this occurrence of {content} to {due}", content = t.content, due = new_due);
+                true
+            },
+            RecurringPolicy::Skip => {
+                // Closing the task would move it on too but Todoist counts that as a completion
+                let after = t.due.date.date().max(config.day.today());
+                let Some(next) = recurring::next_occurrence(&due_string, t.due.date.date(), after) else {
+                    println!("Left recurring task {content} in place as the next occurrence of {due_string} can't be worked out", content = t.content);
+                    return false;
+                };
+                // Sent like a single occurrence so the rule and its anchor stay as they are
+                let skip = DueUpdate {
+                    date: Some(t.due.date.on_date(next).to_api_string()),
+                    timezone: t.due.date.timezone(),
+                    string: Some(due_string),
+                    lang: t.due.lang.to_owned(),
+                    is_recurring: true,
+                };
+                update_task::update_task_due(key, &config.api, &t.id, skip).await;
+                println!("Skipped {content} to its next occurrence on {next}", content = t.content, next = next.format(&config.formats.date));
+                true
+            },
+            RecurringPolicy::Leave => {
+                println!("Left recurring task {content} in place", content = t.content);
+                false
+            },
+        },
+        // The string is left out so Todoist describes the new date itself rather than re-applying the old one
+        _ => {
+            update_task::update_task_due(key, &config.api, &t.id, due_update(None)).await;
+            println!("Rescheduled {content} to {due}", content = t.content, due = new_due);
+            true
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use api::filter_tasks::Task;
+use std::collections::HashSet;
+
+// A task along with its subtasks from the same set of tasks, moved as one so a parent and its subtasks stay together
+#[derive(Debug)]
+pub struct TaskTree<'a> {
+    /// The root first, then its subtasks in the order they were found
+    pub tasks: Vec<&'a Task>,
+}
+
+impl<'a> TaskTree<'a> {
+    pub fn root(&self) -> &'a Task {
+        self.tasks[0]
+    }
+
+    // The number of tasks the tree counts as toward goal based limits
+    pub fn size(&self, count_once: bool) -> i32 {
+        if count_once {
+            1
+        }
+        else {
+            self.tasks.len() as i32
+        }
+    }
+}
+
+// Group the tasks into trees, any task whose parent isn't in the set is the root of its own tree
+pub fn build_trees<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Vec<TaskTree<'a>> {
+    let tasks: Vec<&Task> = tasks.into_iter().collect();
+    let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let mut trees: Vec<TaskTree> = tasks.iter()
+        .filter(|t| t.parent_id.as_deref().is_none_or(|p| !ids.contains(p)))
+        .map(|t| TaskTree { tasks: vec![*t] })
+        .collect();
+    for tree in trees.iter_mut() {
+        // Walk down a level at a time, which also guards against a cycle
+        let mut i = 0;
+        while i < tree.tasks.len() {
+            let parent = tree.tasks[i].id.as_str();
+            let children: Vec<&Task> = tasks.iter()
+                .filter(|t| t.parent_id.as_deref() == Some(parent))
+                .filter(|t| !tree.tasks.iter().any(|c| c.id == t.id))
+                .copied()
+                .collect();
+            tree.tasks.extend(children);
+            i += 1;
+        }
+    }
+    trees
+}
+
+// Only the trees whose every task can be moved, printing the ones held back by a task that stays
+pub fn movable_trees<'a>(trees: Vec<TaskTree<'a>>, movable: &[&Task]) -> Vec<TaskTree<'a>> {
+    let movable_ids: HashSet<&str> = movable.iter().map(|t| t.id.as_str()).collect();
+    trees.into_iter()
+        .filter(|tree| {
+            let moved = tree.tasks.iter().filter(|t| movable_ids.contains(t.id.as_str())).count();
+            if moved > 0 && moved < tree.tasks.len() {
+                println!("Left {content} and its subtasks in place as some of them stay", content = tree.root().content);
+            }
+            moved == tree.tasks.len()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::task;
+
+    fn ids(tree: &TaskTree) -> Vec<String> {
+        tree.tasks.iter().map(|t| t.id.clone()).collect()
+    }
+
+    #[test]
+    fn test_subtasks_join_their_parent() {
+        let tasks = [
+            Task { parent_id: Some("parent".to_string()), ..task("child") },
+            task("parent"),
+            Task { parent_id: Some("child".to_string()), ..task("grandchild") },
+            task("other"),
+        ];
+        let trees = build_trees(tasks.iter());
+        assert_eq!(trees.len(), 2);
+        assert_eq!(ids(&trees[0]), vec!["parent", "child", "grandchild"]);
+        assert_eq!(ids(&trees[1]), vec!["other"]);
+        assert_eq!(trees[0].size(false), 3);
+        assert_eq!(trees[0].size(true), 1);
+    }
+
+    #[test]
+    fn test_subtask_without_its_parent_is_a_root() {
+        let tasks = [Task { parent_id: Some("elsewhere".to_string()), ..task("child") }];
+        let trees = build_trees(tasks.iter());
+        assert_eq!(ids(&trees[0]), vec!["child"]);
+    }
+
+    #[test]
+    fn test_tree_only_moves_when_every_task_can() {
+        let tasks = [task("parent"), Task { parent_id: Some("parent".to_string()), ..task("child") }, task("other")];
+        let trees = build_trees(tasks.iter());
+        let movable = vec![&tasks[0], &tasks[2]];
+        let moved = movable_trees(trees, &movable);
+        assert_eq!(moved.len(), 1);
+        assert_eq!(ids(&moved[0]), vec!["other"]);
+    }
+}
@@ -0,0 +1,58 @@
+use api::filter_tasks::{Deadline, Due, DueDate, Duration, Task, UndatedTask};
+use chrono::NaiveDate;
+
+// A plain task due on the 4th of August 2025, tests set the fields they care about with struct update syntax
+pub fn task(id: &str) -> Task {
+    Task {
+        id: id.to_string(),
+        due: due(DueDate::Date(NaiveDate::from_ymd_opt(2025, 8, 4).unwrap())),
+        content: id.to_string(),
+        priority: 1,
+        duration: None,
+        project_id: "inbox".to_string(),
+        section_id: None,
+        parent_id: None,
+        labels: Vec::new(),
+        day_order: 0,
+        added_at: None,
+        deadline: None,
+    }
+}
+
+// A plain task waiting in the Inbox without a due date
+pub fn undated_task(id: &str) -> UndatedTask {
+    UndatedTask {
+        id: id.to_string(),
+        due: None,
+        content: id.to_string(),
+        priority: 1,
+        project_id: "inbox".to_string(),
+        section_id: None,
+        parent_id: None,
+        labels: Vec::new(),
+        added_at: None,
+        deadline: None,
+    }
+}
+
+// A due date that doesn't recur
+pub fn due(date: DueDate) -> Due {
+    Due {
+        date,
+        string: None,
+        lang: None,
+        is_recurring: false,
+    }
+}
+
+pub fn deadline(date: &str) -> Option<Deadline> {
+    Some(Deadline { date: date.to_string(), lang: None })
+}
+
+pub fn minutes(amount: i32) -> Option<Duration> {
+    Some(Duration { amount, unit: "minute".to_string() })
+}
+
+pub fn labels(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|l| l.to_string()).collect()
+}
@@ -0,0 +1,187 @@
+use api::filter_tasks::{self, DueDate, Task};
+use api::update_task::{self, TaskUpdate};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use config::settings::Config;
+
+use crate::protection::Protection;
+use crate::reschedule;
+
+// A span of the day that is already taken
+type Busy = (NaiveTime, NaiveTime);
+
+// A task given a start time
+#[derive(Debug)]
+pub struct Block<'a> {
+    pub task: &'a Task,
+    pub start: NaiveTime,
+    pub minutes: i32,
+}
+
+// Give today's tasks without a time a start time in the working window, previewing unless apply is set
+pub async fn time_block(key: &str, config: &Config, today: NaiveDate, apply: bool) {
+    let todays_tasks = filter_tasks::get_todays_tasks(key, &config.api, today).await;
+    let (start, end) = config.time_block.window().expect("The time block window should be validated");
+    // Nothing is put in the past when blocking out the current day
+    let now = Local::now().naive_local();
+    let from = if config.day.day_of(now) == today { first_start(now, today, start, end) } else { Some(start) };
+    let Some(from) = from else {
+        println!("The time block window has already passed for today");
+        return;
+    };
+
+    // Breaks and tasks that already have a time are kept free
+    let mut busy: Vec<Busy> = config.time_block.breaks();
+    for t in todays_tasks.iter() {
+        if let Some(time) = t.due.date.local_time() {
+            busy.push((time, add_minutes(time, minutes_for(t, config))));
+        }
+    }
+
+    // Recurring tasks keep their rule as it is, protected tasks stay as they are
+    let mut to_block = Protection::new(&config.protect).unprotected(todays_tasks.iter()
+        .filter(|t| !t.due.date.has_time())
+        .filter(|t| !t.due.is_recurring));
+    // The highest priority tasks get the earliest slots
+    to_block.sort_by_key(|t| -t.priority);
+
+    let (blocks, unplaced) = pack(&to_block, &busy, from, end, |t| minutes_for(t, config));
+    for b in blocks.iter() {
+        println!("{start}-{end}  {content}", start = b.start.format("%H:%M"), end = add_minutes(b.start, b.minutes).format("%H:%M"), content = b.task.content);
+    }
+    for t in unplaced.iter() {
+        println!("No room for {content}", content = t.content);
+    }
+    if !apply {
+        println!("Preview only, run with --apply to set these times");
+        return;
+    }
+    for b in blocks.iter() {
+        reschedule::set_due(key, config, b.task, DueDate::Floating(today.and_time(b.start))).await;
+        // Tasks without a duration are given the default so the block shows in Todoist
+        if b.task.duration.is_none() {
+            let update = TaskUpdate {
+                duration: Some(b.minutes),
+                duration_unit: Some("minute".to_string()),
+                ..TaskUpdate::default()
+            };
+            update_task::update_task(key, &config.api, &b.task.id, &update).await;
+        }
+    }
+}
+
+fn minutes_for(t: &Task, config: &Config) -> i32 {
+    t.duration.as_ref().map(|d| d.minutes()).unwrap_or(config.time_block.default_minutes)
+}
+
+// Place each task in order at the earliest free time that fits it, returning the tasks that don't fit
+fn pack<'a>(tasks: &[&'a Task], busy: &[Busy], from: NaiveTime, end: NaiveTime, minutes: impl Fn(&Task) -> i32) -> (Vec<Block<'a>>, Vec<&'a Task>) {
+    let mut busy: Vec<Busy> = busy.to_vec();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut unplaced: Vec<&Task> = Vec::new();
+    for t in tasks.iter() {
+        let length = minutes(t);
+        match earliest_free(&busy, from, end, length) {
+            Some(start) => {
+                busy.push((start, add_minutes(start, length)));
+                blocks.push(Block { task: t, start, minutes: length });
+            },
+            None => unplaced.push(t),
+        }
+    }
+    blocks.sort_by_key(|b| b.start);
+    (blocks, unplaced)
+}
+
+fn earliest_free(busy: &[Busy], from: NaiveTime, end: NaiveTime, length: i32) -> Option<NaiveTime> {
+    let mut sorted: Vec<Busy> = busy.to_vec();
+    sorted.sort();
+    let mut start = from;
+    for (busy_start, busy_end) in sorted.iter() {
+        if *busy_end <= start {
+            continue;
+        }
+        if fits(start, *busy_start, length) {
+            break;
+        }
+        start = start.max(*busy_end);
+    }
+    fits(start, end, length).then_some(start)
+}
+
+// Whether a task of the length fits between the two times without running past midnight
+fn fits(start: NaiveTime, end: NaiveTime, length: i32) -> bool {
+    (end - start).num_minutes() >= length as i64 && add_minutes(start, length) >= start
+}
+
+fn add_minutes(time: NaiveTime, minutes: i32) -> NaiveTime {
+    time.overflowing_add_signed(TimeDelta::minutes(minutes as i64)).0
+}
+
+// The earliest a block can start today, None once the window is over, including after midnight before the day start
+fn first_start(now: NaiveDateTime, today: NaiveDate, start: NaiveTime, end: NaiveTime) -> Option<NaiveTime> {
+    if now.date() > today {
+        return None;
+    }
+    let from = start.max(round_up(now.time()));
+    // Rounding up just before midnight wraps around to the start of the day
+    Some(from).filter(|from| *from >= now.time() && *from < end)
+}
+
+// Round up to the next five minutes so blocks start on tidy times
+fn round_up(time: NaiveTime) -> NaiveTime {
+    let minutes = (time.hour() * 60 + time.minute()).div_ceil(5) * 5;
+    NaiveTime::from_num_seconds_from_midnight_opt(minutes * 60, 0).unwrap_or(NaiveTime::MIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, task};
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn minutes(t: &Task) -> i32 {
+        t.duration.as_ref().unwrap().minutes()
+    }
+
+    #[test]
+    fn test_tasks_pack_around_busy_times() {
+        let a = Task { duration: test_support::minutes(60), ..task("a") };
+        let b = Task { duration: test_support::minutes(45), ..task("b") };
+        let c = Task { duration: test_support::minutes(30), ..task("c") };
+        let busy = vec![(time(10, 30), time(11, 0)), (time(12, 0), time(13, 0))];
+        let (blocks, unplaced) = pack(&[&a, &b, &c], &busy, time(9, 0), time(17, 0), minutes);
+        let starts: Vec<(&str, NaiveTime)> = blocks.iter().map(|b| (b.task.id.as_str(), b.start)).collect();
+        // b doesn't fit in the 30 minutes before 10:30 but c does
+        assert_eq!(starts, vec![("a", time(9, 0)), ("c", time(10, 0)), ("b", time(11, 0))]);
+        assert!(unplaced.is_empty());
+    }
+
+    #[test]
+    fn test_tasks_that_dont_fit_are_returned() {
+        let (a, b) = (Task { duration: test_support::minutes(60), ..task("a") }, Task { duration: test_support::minutes(60), ..task("b") });
+        let (blocks, unplaced) = pack(&[&a, &b], &[], time(16, 0), time(17, 0), minutes);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(unplaced[0].id, "b");
+    }
+
+    #[test]
+    fn test_blocks_start_from_now_until_the_window_is_over() {
+        let today = NaiveDate::from_ymd_opt(2025, 8, 4).unwrap();
+        let at = |day: u32, hour: u32, minute: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap().and_hms_opt(hour, minute, 0).unwrap();
+        assert_eq!(first_start(at(4, 7, 0), today, time(9, 0), time(17, 0)), Some(time(9, 0)));
+        assert_eq!(first_start(at(4, 10, 2), today, time(9, 0), time(17, 0)), Some(time(10, 5)));
+        assert_eq!(first_start(at(4, 17, 0), today, time(9, 0), time(17, 0)), None);
+        assert_eq!(first_start(at(4, 23, 58), today, time(9, 0), time(23, 59)), None);
+        // Still today with a later day start, but the window was yesterday evening
+        assert_eq!(first_start(at(5, 1, 0), today, time(9, 0), time(17, 0)), None);
+    }
+
+    #[test]
+    fn test_round_up_to_five_minutes() {
+        assert_eq!(round_up(time(9, 1)), time(9, 5));
+        assert_eq!(round_up(time(9, 5)), time(9, 5));
+    }
+}
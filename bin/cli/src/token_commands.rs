@@ -0,0 +1,29 @@
+use config::settings::DbConfig;
+use std::io::{self, BufRead};
+use std::process;
+use token::encrypted_store;
+
+// The token is read from stdin so it never ends up in shell history
+pub fn store(config: &DbConfig) {
+    println!("Enter the API token:");
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line).expect("Failed to read the token");
+    let token = line.trim();
+    if token.is_empty() {
+        eprintln!("No token entered");
+        process::exit(1)
+    }
+    if let Err(e) = encrypted_store::save_token(token, config) {
+        eprintln!("{e}");
+        process::exit(1)
+    }
+    println!("Stored the token, set token.source = \"store\" to use it");
+}
+
+pub fn clear(config: &DbConfig) {
+    if let Err(e) = encrypted_store::purge(config) {
+        eprintln!("{e}");
+        process::exit(1)
+    }
+    println!("Cleared the stored token");
+}
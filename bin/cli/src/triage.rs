@@ -0,0 +1,151 @@
+use api::completed_fetch;
+use api::filter_tasks::{self, UndatedTask};
+use api::update_task::{self, DueUpdate};
+use chrono::NaiveDate;
+use config::settings::Config;
+use std::collections::HashMap;
+
+use crate::busy_days::BusyDays;
+use crate::load_balance::{self, DayLoad};
+use crate::protection::Protection;
+use crate::working_days::WorkingDays;
+
+// Give undated tasks from the triage filter a due date on the days that are under target, so the backlog fills the gaps
+pub async fn triage(key: &str, config: &Config, today: NaiveDate) {
+    let undated = filter_tasks::get_undated_tasks(key, &config.api, &config.triage.filter).await;
+    if undated.is_empty() {
+        println!("No undated tasks to schedule");
+        return;
+    }
+    let working_days = WorkingDays::load(config);
+    let mut days: Vec<NaiveDate> = vec![today];
+    days.extend((1..config.triage.days).map(|i| working_days.add_working_days(today, i)));
+    let mut loads = load_balance::loads_on(key, config, &days).await;
+
+    let stats = completed_fetch::get_completed_stats(key, &config.api).await;
+    // Tasks already done today count toward its target
+    if let Some(done) = stats.days_items.iter().find(|x| x.date == today.format("%Y-%m-%d").to_string()) {
+        loads[0].count += done.total_completed;
+    }
+    let daily_cap = config.daily_cap(stats.goals.weekly_goal);
+    let busy_days = BusyDays::load(config, today).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+
+    // Subtasks are scheduled along with their parent, so a group with a protected task is left as it is
+    let unprotected: Vec<&str> = Protection::new(&config.protect).unprotected(undated.iter()).iter().map(|t| t.id.as_str()).collect();
+    let groups: Vec<Vec<&UndatedTask>> = group(&undated).into_iter()
+        .filter(|g| g.iter().all(|t| unprotected.contains(&t.id.as_str())))
+        .collect();
+    // A group is never given a day after the earliest deadline in it
+    let items: Vec<(i32, Option<NaiveDate>)> = groups.iter()
+        .map(|g| (
+            if config.tasks.count_tree_once { 1 } else { g.len() as i32 },
+            g.iter().filter_map(|t| t.deadline.as_ref().and_then(|d| d.date())).min(),
+        ))
+        .collect();
+    let assigned = schedule(&mut loads, &items, |d| busy_days.scale(daily_cap, d));
+    let mut unscheduled = 0;
+    for (group, day) in groups.iter().zip(assigned) {
+        let Some(day) = day else {
+            unscheduled += group.len();
+            continue;
+        };
+        for t in group.iter() {
+            let due = DueUpdate {
+                date: Some(day.format("%Y-%m-%d").to_string()),
+                timezone: None,
+                string: None,
+                lang: None,
+                is_recurring: false,
+            };
+            update_task::update_task_due(key, &config.api, &t.id, due).await;
+            println!("Scheduled {content} for {day}", content = t.content, day = day.format(&config.formats.date));
+        }
+    }
+    if unscheduled > 0 {
+        println!("Left {unscheduled} tasks undated as the days before their deadline are already at their target");
+    }
+}
+
+// Each task with its undated subtasks, highest priority then oldest first
+fn group(tasks: &[UndatedTask]) -> Vec<Vec<&UndatedTask>> {
+    let by_id: HashMap<&str, &UndatedTask> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let root_of = |t: &UndatedTask| {
+        let mut root = t;
+        while let Some(parent) = root.parent_id.as_deref().and_then(|p| by_id.get(p)) {
+            root = parent;
+        }
+        root.id.clone()
+    };
+    let mut groups: Vec<Vec<&UndatedTask>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for t in tasks.iter() {
+        let root = root_of(t);
+        match index.get(&root) {
+            Some(i) => groups[*i].push(t),
+            None => {
+                index.insert(root, groups.len());
+                groups.push(vec![t]);
+            },
+        }
+    }
+    // The root comes first in each group
+    for g in groups.iter_mut() {
+        g.sort_by_key(|t| t.parent_id.as_deref().is_some_and(|p| by_id.contains_key(p)));
+    }
+    groups.sort_by(|a, b| b[0].priority.cmp(&a[0].priority).then(a[0].added_at.cmp(&b[0].added_at)));
+    groups
+}
+
+// Put each group of the given size on the day with the most room under its cap up to its latest day,
+// earliest first on a tie, None when no day has room
+fn schedule(loads: &mut [DayLoad], items: &[(i32, Option<NaiveDate>)], cap: impl Fn(NaiveDate) -> i32) -> Vec<Option<NaiveDate>> {
+    items.iter().map(|(size, latest)| {
+        let chosen = loads.iter_mut()
+            .filter(|l| latest.is_none_or(|latest| l.day <= latest))
+            .filter(|l| l.count + size <= cap(l.day))
+            .max_by_key(|l| (cap(l.day) - l.count, std::cmp::Reverse(l.day)))?;
+        chosen.count += size;
+        Some(chosen.day)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::undated_task;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_groups_keep_subtasks_with_their_parent() {
+        let tasks = [
+            UndatedTask { added_at: Some("2025-07-01".to_string()), ..undated_task("a") },
+            UndatedTask { parent_id: Some("c".to_string()), added_at: Some("2025-07-03".to_string()), ..undated_task("b") },
+            UndatedTask { priority: 4, added_at: Some("2025-07-02".to_string()), ..undated_task("c") },
+            UndatedTask { added_at: Some("2025-06-01".to_string()), ..undated_task("d") },
+        ];
+        let ids: Vec<Vec<&str>> = group(&tasks).iter().map(|g| g.iter().map(|t| t.id.as_str()).collect()).collect();
+        assert_eq!(ids, vec![vec!["c", "b"], vec!["d"], vec!["a"]]);
+    }
+
+    #[test]
+    fn test_days_under_target_are_filled_and_the_rest_left() {
+        let mut loads = vec![DayLoad { day: day(4), count: 3 }, DayLoad { day: day(5), count: 1 }];
+        let assigned = schedule(&mut loads, &[(2, None), (1, None), (1, None), (1, None)], |_| 3);
+        assert_eq!(assigned, vec![Some(day(5)), None, None, None]);
+        let mut loads = vec![DayLoad { day: day(4), count: 2 }, DayLoad { day: day(5), count: 1 }];
+        assert_eq!(schedule(&mut loads, &[(1, None), (1, None), (1, None), (1, None)], |_| 3), vec![Some(day(5)), Some(day(4)), Some(day(5)), None]);
+    }
+
+    #[test]
+    fn test_groups_are_never_scheduled_after_their_deadline() {
+        let mut loads = vec![DayLoad { day: day(4), count: 2 }, DayLoad { day: day(5), count: 0 }];
+        assert_eq!(schedule(&mut loads, &[(1, Some(day(4))), (1, Some(day(4))), (1, Some(day(3)))], |_| 3), vec![Some(day(4)), None, None]);
+        assert_eq!(loads[1].count, 0);
+    }
+}
@@ -0,0 +1,118 @@
+use api::filter_tasks;
+use chrono::NaiveDate;
+use config::settings::{CapacityConfig, Config, RecurringPolicy, WipConfig};
+use db::postpone_counts;
+
+use crate::capacity;
+use crate::ordering;
+use crate::protection::Protection;
+use crate::reschedule;
+use crate::task_tree::{self, TaskTree};
+use crate::working_days::WorkingDays;
+
+// Postpone tasks off today until it is within the WIP limit, in the configured order
+// Recurring tasks are handled with the policy of the command the cap is run for
+pub async fn cap_today(key: &str, config: &Config, today: NaiveDate, policy: RecurringPolicy) {
+    if !config.wip.is_limited() {
+        println!("No WIP limit is set, see the wip config");
+        return;
+    }
+    let todays_tasks = filter_tasks::get_todays_tasks(key, &config.api, today).await;
+    // Subtasks can be counted along with their parent as a single task
+    let count_once = config.tasks.count_tree_once;
+    let count: i32 = task_tree::build_trees(todays_tasks.iter()).iter().map(|tree| tree.size(count_once)).sum();
+    let minutes: i32 = todays_tasks.iter().map(|t| capacity::task_minutes(t, &config.capacity)).sum();
+    if within(&config.wip, count, minutes) {
+        log::debug!("Today has {count} tasks taking {minutes} minutes, within the WIP limit");
+        return;
+    }
+
+    // Tasks at a set time are blocked out so stay, as do protected tasks
+    let candidates = Protection::new(&config.protect).unprotected(todays_tasks.iter()
+        .filter(|t| !t.due.date.has_time())
+        .filter(|t| !reschedule::is_left_in_place(t, policy)));
+    // Subtasks move with their parent so only whole trees can be moved
+    let mut trees = task_tree::movable_trees(task_tree::build_trees(todays_tasks.iter()), &candidates);
+    let counts = postpone_counts::get_postpone_counts(&config.db).expect("Failed to load postpone counts");
+    ordering::sort_trees(&mut trees, &config.postpone, &counts);
+    let to_postpone = select_over_limit(trees, count, minutes, &config.wip, &config.capacity, count_once);
+    println!("Today is over the WIP limit, postponing {num} tasks", num = to_postpone.iter().map(|tree| tree.tasks.len()).sum::<usize>());
+
+    let working_days = WorkingDays::load(config);
+    for t in to_postpone.iter().flat_map(|tree| tree.tasks.iter()) {
+        reschedule::postpone_task_to_tomorrow(key, config, &working_days, t, policy).await;
+    }
+}
+
+fn within(config: &WipConfig, count: i32, minutes: i32) -> bool {
+    config.max_tasks.is_none_or(|m| count <= m) && config.max_minutes.is_none_or(|m| minutes <= m)
+}
+
+// Take trees in order until what is left today is within the limit
+// A tree is only taken when it brings down a limit that is still exceeded
+fn select_over_limit<'a>(trees: Vec<TaskTree<'a>>, mut count: i32, mut minutes: i32, config: &WipConfig, capacity: &CapacityConfig, count_once: bool) -> Vec<TaskTree<'a>> {
+    let mut selected: Vec<TaskTree> = Vec::new();
+    for tree in trees.into_iter() {
+        if within(config, count, minutes) {
+            break;
+        }
+        let size = tree.size(count_once);
+        let length = capacity::tree_minutes(&tree, capacity);
+        let over_count = config.max_tasks.is_some_and(|m| count > m);
+        let over_minutes = config.max_minutes.is_some_and(|m| minutes > m);
+        if (over_count && size > 0) || (over_minutes && length > 0) {
+            count -= size;
+            minutes -= length;
+            selected.push(tree);
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{minutes, task};
+    use api::filter_tasks::Task;
+
+    fn ids(trees: &[TaskTree]) -> Vec<String> {
+        trees.iter().map(|tree| tree.root().id.clone()).collect()
+    }
+
+    #[test]
+    fn test_tasks_taken_in_order_until_within_the_count() {
+        let tasks = [Task { duration: minutes(10), ..task("a") }, Task { duration: minutes(10), ..task("b") }, Task { duration: minutes(10), ..task("c") }];
+        let config = WipConfig { max_tasks: Some(4), max_minutes: None };
+        let selected = select_over_limit(task_tree::build_trees(tasks.iter()), 6, 60, &config, &CapacityConfig::default(), false);
+        assert_eq!(ids(&selected), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_both_limits_must_be_met() {
+        let tasks = [Task { duration: minutes(10), ..task("a") }, Task { duration: minutes(40), ..task("b") }, Task { duration: minutes(10), ..task("c") }];
+        let config = WipConfig { max_tasks: Some(3), max_minutes: Some(20) };
+        let selected = select_over_limit(task_tree::build_trees(tasks.iter()), 3, 60, &config, &CapacityConfig::default(), false);
+        assert_eq!(ids(&selected), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_tasks_taking_no_time_stay_when_only_minutes_are_over() {
+        let tasks = [Task { duration: minutes(0), ..task("a") }, Task { duration: minutes(30), ..task("b") }];
+        let config = WipConfig { max_tasks: Some(5), max_minutes: Some(20) };
+        let selected = select_over_limit(task_tree::build_trees(tasks.iter()), 2, 30, &config, &CapacityConfig::default(), false);
+        assert_eq!(ids(&selected), vec!["b"]);
+    }
+
+    #[test]
+    fn test_trees_count_once_when_configured() {
+        let tasks = [
+            Task { duration: minutes(10), ..task("a") },
+            Task { parent_id: Some("a".to_string()), duration: minutes(10), ..task("b") },
+            Task { duration: minutes(10), ..task("c") },
+        ];
+        let config = WipConfig { max_tasks: Some(1), max_minutes: None };
+        // Counted once the tree of two is a single task so taking it is enough
+        let selected = select_over_limit(task_tree::build_trees(tasks.iter()), 2, 30, &config, &CapacityConfig::default(), true);
+        assert_eq!(ids(&selected), vec!["a"]);
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use config::settings::Config;
+use db::exclude_days;
+
+// The days tasks can be postponed onto, skipping excluded days, recurring exclusions and non-working weekdays
+pub struct WorkingDays {
+    excluded_days: Vec<NaiveDate>,
+    non_working_weekdays: Vec<Weekday>,
+    recurring_exclusions: Vec<(u32, u32)>,
+}
+
+impl WorkingDays {
+    pub fn new(excluded_days: Vec<NaiveDate>, non_working_weekdays: Vec<Weekday>, recurring_exclusions: Vec<(u32, u32)>) -> WorkingDays {
+        WorkingDays {
+            excluded_days,
+            non_working_weekdays,
+            recurring_exclusions,
+        }
+    }
+
+    pub fn load(config: &Config) -> WorkingDays {
+        let excluded_days = exclude_days::get_excluded_days(&config.db).expect("Failed to load excluded days");
+        WorkingDays::new(excluded_days, config.schedule.non_working_weekdays(), config.schedule.recurring_exclusions())
+    }
+
+    pub fn is_working_day(&self, day: NaiveDate) -> bool {
+        !self.excluded_days.contains(&day)
+            && !self.non_working_weekdays.contains(&day.weekday())
+            && !self.recurring_exclusions.contains(&(day.month(), day.day()))
+    }
+
+    // Move forward the number of working days, only counting days that are working days
+    pub fn add_working_days(&self, from: NaiveDate, days: u64) -> NaiveDate {
+        let mut day = from;
+        let mut remaining = days;
+        // A year without a working day means the config excludes everything, so fall back to calendar days
+        let mut checked = 0;
+        while remaining > 0 {
+            day = day.succ_opt().unwrap();
+            checked += 1;
+            if checked > 366 {
+                return from.checked_add_days(Days::new(days)).unwrap();
+            }
+            if self.is_working_day(day) {
+                remaining -= 1;
+            }
+        }
+        day
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_friday_postpones_to_monday() {
+        let working_days = WorkingDays::new(Vec::new(), vec![Weekday::Sat, Weekday::Sun], Vec::new());
+        // 2025-08-01 is a Friday
+        assert_eq!(working_days.add_working_days(date(8, 1), 1), date(8, 4));
+        assert_eq!(working_days.add_working_days(date(8, 1), 2), date(8, 5));
+    }
+
+    #[test]
+    fn test_excluded_and_recurring_days_are_skipped() {
+        let working_days = WorkingDays::new(vec![date(12, 24)], Vec::new(), vec![(12, 25)]);
+        assert_eq!(working_days.add_working_days(date(12, 23), 1), date(12, 26));
+    }
+
+    #[test]
+    fn test_no_exclusions_is_calendar_days() {
+        let working_days = WorkingDays::new(Vec::new(), Vec::new(), Vec::new());
+        assert_eq!(working_days.add_working_days(date(8, 1), 3), date(8, 4));
+        assert_eq!(working_days.add_working_days(date(8, 1), 0), date(8, 1));
+    }
+}
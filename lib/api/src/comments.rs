@@ -0,0 +1,29 @@
+use config::settings::ApiConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CommentRequest {
+    task_id: String,
+    content: String,
+}
+
+pub async fn add_comment(key : &str,
+                         config: &ApiConfig,
+                         task_id: &str,
+                         content: &str) {
+    let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
+        .post(config.base_url.to_owned() + "/comments")
+        .header("Authorization", "Bearer ".to_owned() + key)
+        .json(&CommentRequest { task_id: task_id.to_string(), content: content.to_string() })
+        .send()
+        .await;
+    if req.is_err() {
+        log::error!("Failed to send the request for adding a comment: {}", req.err().unwrap());
+        panic!("Failed to send the request for adding a comment")
+    }
+    let response = req.unwrap();
+    if !response.status().is_success() {
+        log::error!("Failed to add comment, status: {}", response.status());
+        panic!("Failed to add the comment")
+    }
+}
@@ -1,3 +1,4 @@
+use config::settings::ApiConfig;
 use serde::{Deserialize, Serialize};
 
 // Completed Stats Request
@@ -26,9 +27,9 @@ pub struct CompletedStats {
     pub goals: Goals,
 }
 
-pub async fn get_completed_stats(key : &str) -> CompletedStats {
+pub async fn get_completed_stats(key : &str, config: &ApiConfig) -> CompletedStats {
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .get("https://api.todoist.com/api/v1/tasks/completed/stats")
+        .get(config.base_url.to_owned() + "/tasks/completed/stats")
         .header("Authorization", "Bearer ".to_owned() + key)
         .send()
         .await;
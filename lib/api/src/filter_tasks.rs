@@ -1,11 +1,155 @@
+use chrono::{DateTime, Days, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use config::settings::ApiConfig;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+// The three kinds of due date Todoist supports
+#[derive(Debug, Clone, PartialEq)]
+pub enum DueDate {
+    /// A whole day with no time
+    Date(NaiveDate),
+    /// A time of day with no timezone, it stays at the same wall clock time wherever the user is
+    Floating(NaiveDateTime),
+    /// An exact moment in a fixed timezone
+    Fixed(DateTime<Tz>),
+}
+
+impl DueDate {
+    pub fn parse(date: &str, timezone: Option<&str>) -> Result<DueDate, String> {
+        if let Some(utc) = date.strip_suffix('Z') {
+            let naive = NaiveDateTime::parse_from_str(utc, "%Y-%m-%dT%H:%M:%S%.f")
+                .map_err(|e| format!("Failed to parse due date {date}: {e}"))?;
+            // Without a known timezone the moment is still right, it is just shown in UTC
+            let tz: Tz = timezone.and_then(|t| t.parse().ok()).unwrap_or(Tz::UTC);
+            Ok(DueDate::Fixed(Utc.from_utc_datetime(&naive).with_timezone(&tz)))
+        }
+        else if date.contains('T') {
+            NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(DueDate::Floating)
+                .map_err(|e| format!("Failed to parse due date {date}: {e}"))
+        }
+        else {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(DueDate::Date)
+                .map_err(|e| format!("Failed to parse due date {date}: {e}"))
+        }
+    }
+
+    // The date in the form the API expects, fixed times are always sent in UTC
+    pub fn to_api_string(&self) -> String {
+        match self {
+            DueDate::Date(d) => d.format("%Y-%m-%d").to_string(),
+            DueDate::Floating(d) => d.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            DueDate::Fixed(d) => d.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        }
+    }
+
+    pub fn timezone(&self) -> Option<String> {
+        match self {
+            DueDate::Fixed(d) => Some(d.timezone().name().to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn has_time(&self) -> bool {
+        !matches!(self, DueDate::Date(_))
+    }
+
+    // The time of day on this computer's clock, None for a whole day
+    pub fn local_time(&self) -> Option<NaiveTime> {
+        match self {
+            DueDate::Date(_) => None,
+            DueDate::Floating(d) => Some(d.time()),
+            DueDate::Fixed(d) => Some(d.with_timezone(&Local).time()),
+        }
+    }
+
+    // The calendar day the task is due on, in the timezone it was set in
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            DueDate::Date(d) => *d,
+            DueDate::Floating(d) => d.date(),
+            DueDate::Fixed(d) => d.date_naive(),
+        }
+    }
+
+    // Move to another day keeping the wall clock time in the task's timezone, so DST changes don't shift the hour
+    pub fn on_date(&self, date: NaiveDate) -> DueDate {
+        match self {
+            DueDate::Date(_) => DueDate::Date(date),
+            DueDate::Floating(d) => DueDate::Floating(date.and_time(d.time())),
+            DueDate::Fixed(d) => DueDate::Fixed(resolve_local(&d.timezone(), date.and_time(d.time()))),
+        }
+    }
+
+    pub fn add_days(&self, days: u64) -> DueDate {
+        self.on_date(self.date().checked_add_days(Days::new(days)).unwrap())
+    }
+}
+
+impl std::fmt::Display for DueDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DueDate::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            DueDate::Floating(d) => write!(f, "{}", d.format("%Y-%m-%d %H:%M")),
+            DueDate::Fixed(d) => write!(f, "{} {}", d.format("%Y-%m-%d %H:%M"), d.timezone().name()),
+        }
+    }
+}
+
+// A wall clock time can be skipped or repeated by a DST change, take the earliest and step over any gap
+fn resolve_local(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(d) => d,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => resolve_local(tz, local + TimeDelta::hours(1)),
+    }
+}
+
 // Filtered Tasks Request
 #[derive(Debug, Serialize, Deserialize)]
+struct RawDue {
+    date: String,
+    timezone: Option<String>,
+    string: Option<String>,
+    lang: Option<String>,
+    #[serde(default)]
+    is_recurring: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawDue", into = "RawDue")]
 pub struct Due {
-    pub date: String,
+    pub date: DueDate,
     pub string: Option<String>,
     pub lang: Option<String>,
+    pub is_recurring: bool,
+}
+
+impl TryFrom<RawDue> for Due {
+    type Error = String;
+
+    fn try_from(raw: RawDue) -> Result<Self, Self::Error> {
+        Ok(Due {
+            date: DueDate::parse(&raw.date, raw.timezone.as_deref())?,
+            string: raw.string,
+            lang: raw.lang,
+            is_recurring: raw.is_recurring,
+        })
+    }
+}
+
+impl From<Due> for RawDue {
+    fn from(due: Due) -> Self {
+        RawDue {
+            date: due.date.to_api_string(),
+            timezone: due.date.timezone(),
+            string: due.string,
+            lang: due.lang,
+            is_recurring: due.is_recurring,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -14,6 +158,28 @@ pub struct Duration {
     pub unit: String,
 }
 
+impl Duration {
+    // The duration in minutes, Todoist only uses minute and day units
+    pub fn minutes(&self) -> i32 {
+        match self.unit.as_str() {
+            "day" => self.amount * 24 * 60,
+            _ => self.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Deadline {
+    pub date: String,
+    pub lang: Option<String>,
+}
+
+impl Deadline {
+    pub fn date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
@@ -21,77 +187,203 @@ pub struct Task {
     pub content: String,
     pub priority: i32,
     pub duration: Option<Duration>,
+    #[serde(default)]
+    pub project_id: String,
+    pub section_id: Option<String>,
+    // Set on subtasks
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    // The position within a day in the Today and Upcoming views
+    #[serde(default)]
+    pub day_order: i32,
+    pub added_at: Option<String>,
+    pub deadline: Option<Deadline>,
 }
 
+// A task that may not have a due date, such as one waiting in the Inbox
 #[derive(Debug, Serialize, Deserialize)]
-struct Response {
-    results: Vec<Task>,
+pub struct UndatedTask {
+    pub id: String,
+    pub due: Option<Due>,
+    pub content: String,
+    pub priority: i32,
+    #[serde(default)]
+    pub project_id: String,
+    pub section_id: Option<String>,
+    // Set on subtasks
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub added_at: Option<String>,
+    pub deadline: Option<Deadline>,
 }
 
-pub async fn get_todays_tasks(key : &str) -> Vec<Task> {
-    let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .get("https://api.todoist.com/api/v1/tasks/filter?query=today&limit=200")
-        .header("Authorization", "Bearer ".to_owned() + key)
-        .send()
-        .await;
+#[derive(Debug, Serialize, Deserialize)]
+struct Response<T> {
+    results: Vec<T>,
+}
 
-    if let Err(r) = req {
-        log::error!("Failed to send the request for today tasks: {}", r);
-        panic!("Failed to send the request for today tasks")
+pub async fn get_todays_tasks(key : &str, config: &ApiConfig, today: NaiveDate) -> Vec<Task> {
+    get_filtered_tasks(key, config, &day_query(today, calendar_today()), "today").await
+}
+
+pub async fn get_overdue_tasks(key : &str, config: &ApiConfig, today: NaiveDate) -> Vec<Task> {
+    get_filtered_tasks(key, config, &overdue_query(today, calendar_today()), "overdue").await
+}
+
+pub async fn get_tomorrow_tasks(key : &str, config: &ApiConfig, today: NaiveDate) -> Vec<Task> {
+    let tomorrow = today.checked_add_days(Days::new(1)).unwrap();
+    get_filtered_tasks(key, config, &day_query(tomorrow, calendar_today()), "tomorrow").await
+}
+
+pub async fn get_tasks_due_on(key : &str, config: &ApiConfig, day: NaiveDate) -> Vec<Task> {
+    get_filtered_tasks(key, config, &day_query(day, calendar_today()), &day.format("%Y-%m-%d").to_string()).await
+}
+
+// Tasks whose deadline has already passed
+pub async fn get_missed_deadline_tasks(key : &str, config: &ApiConfig, today: NaiveDate) -> Vec<Task> {
+    get_filtered_tasks(key, config, &missed_deadline_query(today), "missed deadline").await
+}
+
+// Tasks matching the filter that have no due date, the filter is expected to ask for them with no date
+pub async fn get_undated_tasks(key : &str, config: &ApiConfig, filter: &str) -> Vec<UndatedTask> {
+    let tasks: Vec<UndatedTask> = get_filtered_tasks(key, config, filter, "undated").await;
+    tasks.into_iter().filter(|t| t.due.is_none()).collect()
+}
+
+fn calendar_today() -> NaiveDate {
+    Local::now().naive_local().date()
+}
+
+// Todoist's today follows the calendar, so named days are only used when the tracker's day matches it
+fn day_query(day: NaiveDate, calendar_today: NaiveDate) -> String {
+    if day == calendar_today {
+        "today".to_string()
     }
-    let response: Result<Response, reqwest::Error> = req.unwrap()
-        .json()
-        .await;
+    else if Some(day) == calendar_today.checked_add_days(Days::new(1)) {
+        "tomorrow".to_string()
+    }
+    else {
+        day.format("%b %-d %Y").to_string()
+    }
+}
 
-    if let Err(r) = response {
-        log::error!("Failed to get a response for today tasks: {}", r);
-        panic!("Failed to get a response for today tasks");
+fn overdue_query(today: NaiveDate, calendar_today: NaiveDate) -> String {
+    if today == calendar_today {
+        "overdue".to_string()
+    }
+    else {
+        format!("due before: {}", today.format("%b %-d %Y"))
     }
+}
 
-    response.unwrap().results
+fn missed_deadline_query(today: NaiveDate) -> String {
+    format!("deadline before: {}", today.format("%b %-d %Y"))
 }
 
-pub async fn get_overdue_tasks(key : &str) -> Vec<Task> {
+async fn get_filtered_tasks<T: DeserializeOwned>(key : &str, config: &ApiConfig, query: &str, name: &str) -> Vec<T> {
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .get("https://api.todoist.com/api/v1/tasks/filter?query=overdue&limit=200")
+        .get(config.base_url.to_owned() + "/tasks/filter")
+        .query(&[("query", query.to_string()), ("limit", config.filter_limit.to_string())])
         .header("Authorization", "Bearer ".to_owned() + key)
         .send()
         .await;
+
     if let Err(r) = req {
-        log::error!("Failed to send the request for overdue tasks: {}", r);
-        panic!("Failed to send the request for overdue tasks")
+        log::error!("Failed to send the request for {} tasks: {}", name, r);
+        panic!("Failed to send the request for {} tasks", name)
     }
-
-    let response: Result<Response, reqwest::Error> = req.unwrap()
+    let response: Result<Response<T>, reqwest::Error> = req.unwrap()
         .json()
         .await;
+
     if let Err(r) = response {
-        log::error!("Failed to get a response for overdue tasks: {}", r);
-        panic!("Failed to get a response for overdue tasks");
+        log::error!("Failed to get a response for {} tasks: {}", name, r);
+        panic!("Failed to get a response for {} tasks", name);
     }
 
     response.unwrap().results
 }
 
-pub async fn get_tomorrow_tasks(key : &str) -> Vec<Task> {
-    let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .get("https://api.todoist.com/api/v1/tasks/filter?query=tomorrow&limit=200")
-        .header("Authorization", "Bearer ".to_owned() + key)
-        .send()
-        .await;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Err(r) = req {
-        log::error!("Failed to send the request for tomorrow tasks: {}", r);
-        panic!("Failed to send the request for tomorrow tasks")
+    #[test]
+    fn test_parse_kinds() {
+        assert!(matches!(DueDate::parse("2025-03-28", None).unwrap(), DueDate::Date(_)));
+        assert!(matches!(DueDate::parse("2025-03-28T09:00:00", None).unwrap(), DueDate::Floating(_)));
+        assert!(matches!(DueDate::parse("2025-03-28T09:00:00Z", Some("Europe/London")).unwrap(), DueDate::Fixed(_)));
+        assert!(matches!(DueDate::parse("2025-03-28T09:00:00.000000Z", None).unwrap(), DueDate::Fixed(_)));
     }
-    let response: Result<Response, reqwest::Error> = req.unwrap()
-        .json()
-        .await;
 
-    if let Err(r) = response {
-        log::error!("Failed to get a response for tomorrow tasks: {}", r);
-        panic!("Failed to get a response for tomorrow tasks");
+    #[test]
+    fn test_fixed_is_shown_in_its_timezone() {
+        let due = DueDate::parse("2025-07-01T09:00:00Z", Some("Europe/London")).unwrap();
+        assert_eq!(due.to_string(), "2025-07-01 10:00 Europe/London");
+        assert_eq!(due.to_api_string(), "2025-07-01T09:00:00Z");
+        assert_eq!(due.timezone(), Some("Europe/London".to_string()));
     }
 
-    response.unwrap().results
-}
\ No newline at end of file
+    #[test]
+    fn test_fixed_keeps_wall_clock_across_dst() {
+        // 09:00 in London on the Friday before the clocks go forward is 09:00 UTC, the Monday after is 08:00 UTC
+        let due = DueDate::parse("2025-03-28T09:00:00Z", Some("Europe/London")).unwrap();
+        assert_eq!(due.add_days(3).to_api_string(), "2025-03-31T08:00:00Z");
+    }
+
+    #[test]
+    fn test_floating_keeps_wall_clock() {
+        let due = DueDate::parse("2025-03-28T09:00:00", None).unwrap();
+        assert_eq!(due.add_days(3).to_api_string(), "2025-03-31T09:00:00");
+    }
+
+    #[test]
+    fn test_time_in_dst_gap_moves_forward() {
+        // 01:30 doesn't exist in London on the day the clocks go forward
+        let due = DueDate::parse("2025-03-29T01:30:00Z", Some("Europe/London")).unwrap();
+        assert_eq!(due.add_days(1).to_string(), "2025-03-30 02:30 Europe/London");
+    }
+
+    #[test]
+    fn test_queries_use_names_only_on_calendar_days() {
+        let calendar_today = NaiveDate::from_ymd_opt(2025, 7, 29).unwrap();
+        let tracker_today = NaiveDate::from_ymd_opt(2025, 7, 28).unwrap();
+        assert_eq!(day_query(calendar_today, calendar_today), "today");
+        assert_eq!(day_query(calendar_today.succ_opt().unwrap(), calendar_today), "tomorrow");
+        assert_eq!(day_query(tracker_today, calendar_today), "Jul 28 2025");
+        assert_eq!(overdue_query(calendar_today, calendar_today), "overdue");
+        assert_eq!(overdue_query(tracker_today, calendar_today), "due before: Jul 28 2025");
+        assert_eq!(missed_deadline_query(tracker_today), "deadline before: Jul 28 2025");
+    }
+
+    #[test]
+    fn test_duration_minutes() {
+        assert_eq!(Duration { amount: 45, unit: "minute".to_string() }.minutes(), 45);
+        assert_eq!(Duration { amount: 2, unit: "day".to_string() }.minutes(), 2880);
+    }
+
+    #[test]
+    fn test_deadline_is_read_from_tasks() {
+        let json = r#"{"id":"1","due":{"date":"2025-07-28","is_recurring":false},"content":"Tidy desk","priority":1,"duration":null,"deadline":{"date":"2025-08-01","lang":"en"}}"#;
+        let task: Task = serde_json::from_str(json).unwrap();
+        assert_eq!(task.deadline.unwrap().date(), NaiveDate::from_ymd_opt(2025, 8, 1));
+    }
+
+    #[test]
+    fn test_undated_task_is_read_without_a_due() {
+        let json = r#"{"id":"1","due":null,"content":"Tidy desk","priority":1,"parent_id":null,"added_at":"2025-07-28T09:00:00Z"}"#;
+        let task: UndatedTask = serde_json::from_str(json).unwrap();
+        assert!(task.due.is_none());
+        assert!(task.deadline.is_none());
+    }
+
+    #[test]
+    fn test_due_round_trips_through_json() {
+        let json = r#"{"date":"2025-07-01T09:00:00Z","timezone":"Europe/London","string":"every day at 10am","lang":"en","is_recurring":true}"#;
+        let due: Due = serde_json::from_str(json).unwrap();
+        assert!(due.is_recurring);
+        assert_eq!(serde_json::to_string(&due).unwrap(), json);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod comments;
 pub mod completed_fetch;
 pub mod filter_tasks;
 pub mod update_task;
-pub mod update_goals;
\ No newline at end of file
+pub mod update_goals;
+pub mod user;
\ No newline at end of file
@@ -1,4 +1,5 @@
 use uuid::Uuid; 
+use config::settings::ApiConfig;
 use serde::{Deserialize, Serialize};
 
 // Update Daily Goals Request
@@ -21,6 +22,7 @@ struct DailyRequest {
 }
 
 pub async fn update_daily_goal(key : &str, 
+                                config: &ApiConfig,
                                 daily_goal: &i32) {
     // Make up the json payload
     let payload = DailyRequest{
@@ -36,7 +38,7 @@ pub async fn update_daily_goal(key : &str,
     };
     
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .post("https://api.todoist.com/api/v1/sync")
+        .post(config.base_url.to_owned() + "/sync")
         .header("Authorization", "Bearer ".to_owned() + key)
         .json(&payload)
         .send()
@@ -72,6 +74,7 @@ struct WeeklyRequest {
 }
 
 pub async fn update_weekly_goal(key : &str, 
+                                config: &ApiConfig,
                                 weekly_goal: &i32) {
     // Make up the json payload
     let payload = WeeklyRequest{
@@ -87,7 +90,7 @@ pub async fn update_weekly_goal(key : &str,
     };
     
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .post("https://api.todoist.com/api/v1/sync")
+        .post(config.base_url.to_owned() + "/sync")
         .header("Authorization", "Bearer ".to_owned() + key)
         .json(&payload)
         .send()
@@ -1,28 +1,130 @@
-use std::collections::HashMap;
+use config::settings::ApiConfig;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-pub async fn update_task_due(   key : &str, 
-                                task_id: &str, 
-                                due_date: String, 
-                                due_lang: Option<String>,
-                                due_string: Option<String>) {
+// Update Task Due Request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DueUpdate {
+    /// The date in the form the API expects, see filter_tasks::DueDate::to_api_string
+    /// Left out to have Todoist work out the date from the string, such as the next occurrence of a recurring task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Only set for fixed timezone dates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Only needed for recurring tasks, sending the unchanged recurrence string keeps the rule as it was
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    pub is_recurring: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DueArgs {
+    id: String,
+    due: DueUpdate,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DueCommand {
+    #[serde(rename = "type")]
+    name: String,
+    uuid: String,
+    args: DueArgs,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DueRequest {
+    commands: Vec<DueCommand>,
+}
+
+// Uses the sync API as it is the only one that can set floating and fixed timezone times as they are
+pub async fn update_task_due(   key : &str,
+                                config: &ApiConfig,
+                                task_id: &str,
+                                due: DueUpdate) {
     // Make up the json payload
-    let mut map = HashMap::new();
-    map.insert("due_date", due_date);
-    if let Some(x) = due_lang {
-        map.insert("due_lang", x);
-    }
-    if let Some(x) = due_string {
-        map.insert("due_string", x);
-    }
-    
+    let payload = DueRequest{
+        commands: vec![
+            DueCommand{
+                name: "item_update".to_string(),
+                uuid: Uuid::new_v4().to_string(),
+                args: DueArgs {
+                    id: task_id.to_string(),
+                    due,
+                }
+            }
+        ]
+    };
+
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
-        .post("https://api.todoist.com/api/v1/tasks/".to_owned() + task_id)
+        .post(config.base_url.to_owned() + "/sync")
         .header("Authorization", "Bearer ".to_owned() + key)
-        .json(&map)
+        .json(&payload)
         .send()
         .await;
     if req.is_err() {
         log::error!("Failed to send the request for updating the task due date: {}", req.err().unwrap());
         panic!("Failed to send the request for updating the task due date")
     }
-}
\ No newline at end of file
+    let response = req.unwrap();
+    if !response.status().is_success() {
+        log::error!("Failed to update task due date, status: {}", response.status());
+        panic!("Failed to update the task due date")
+    }
+}
+
+// Only the fields that are set are changed
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// Replaces all of the task's labels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// Must be sent along with duration_unit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i32>,
+    /// Either minute or day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MoveRequest {
+    project_id: String,
+}
+
+pub async fn update_task(key : &str,
+                         config: &ApiConfig,
+                         task_id: &str,
+                         update: &TaskUpdate) {
+    let req = reqwest::Client::new()
+        .post(config.base_url.to_owned() + "/tasks/" + task_id)
+        .header("Authorization", "Bearer ".to_owned() + key)
+        .json(update);
+    send(req, "updating the task").await;
+}
+
+pub async fn move_task(key : &str,
+                       config: &ApiConfig,
+                       task_id: &str,
+                       project_id: &str) {
+    let req = reqwest::Client::new()
+        .post(config.base_url.to_owned() + "/tasks/" + task_id + "/move")
+        .header("Authorization", "Bearer ".to_owned() + key)
+        .json(&MoveRequest { project_id: project_id.to_string() });
+    send(req, "moving the task").await;
+}
+
+async fn send(req: reqwest::RequestBuilder, action: &str) {
+    let response = req.send().await.unwrap_or_else(|e| {
+        log::error!("Failed to send the request for {action}: {e}");
+        panic!("Failed to send the request for {action}")
+    });
+    if !response.status().is_success() {
+        log::error!("Failed {action}, status: {}", response.status());
+        panic!("Failed {action}")
+    }
+}
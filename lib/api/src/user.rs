@@ -0,0 +1,35 @@
+use chrono::Weekday;
+use config::settings::ApiConfig;
+use serde::{Deserialize, Serialize};
+
+// User Request
+#[derive(Debug, Serialize, Deserialize)]
+struct User {
+    // 1 is Monday through to 7 for Sunday
+    start_day: u8,
+}
+
+pub async fn get_week_start(key : &str, config: &ApiConfig) -> Weekday {
+    let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
+        .get(config.base_url.to_owned() + "/user")
+        .header("Authorization", "Bearer ".to_owned() + key)
+        .send()
+        .await;
+
+    if let Err(r) = req {
+        log::error!("Failed to send the request for the user: {}", r);
+        panic!("Failed to send the request for the user")
+    }
+    let response: Result<User, reqwest::Error> = req.unwrap()
+        .json()
+        .await;
+
+    if let Err(r) = response {
+        log::error!("Failed to get a response for the user: {}", r);
+        panic!("Failed to get a response for the user");
+    }
+    let start_day = response.unwrap().start_day;
+    log::trace!("Read the week start day {} from the user settings", start_day);
+
+    Weekday::try_from(start_day.saturating_sub(1)).unwrap_or(Weekday::Mon)
+}
\ No newline at end of file
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
+use std::fs;
+use std::path::Path;
+
+// When an exported item happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum When {
+    /// A whole day, exported as an all day to-do
+    Day(NaiveDate),
+    /// A local time, exported as an event of the given length
+    Floating(NaiveDateTime, i32),
+    /// An exact moment, exported as an event of the given length
+    Fixed(DateTime<Utc>, i32),
+}
+
+// A task to put in the exported calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// A stable id so calendar clients update the same entry each time the file is regenerated
+    pub id: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub when: When,
+}
+
+// The calendar text for the items, the same items and stamp always give the same text
+pub fn to_ics(items: &[Item], stamp: DateTime<Utc>) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//todoist-tracker//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let mut sorted: Vec<&Item> = items.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+    for item in sorted.into_iter() {
+        let component = match item.when {
+            When::Day(_) => "VTODO",
+            _ => "VEVENT",
+        };
+        lines.push(format!("BEGIN:{component}"));
+        lines.push(format!("UID:{}@todoist-tracker", item.id));
+        lines.push(format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")));
+        match &item.when {
+            When::Day(day) => {
+                // A date DUE is exclusive so the whole day is covered by due the day after
+                let due = day.checked_add_days(Days::new(1)).unwrap();
+                lines.push(format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")));
+                lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+            },
+            When::Floating(start, minutes) => {
+                lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+                lines.push(format!("DURATION:PT{minutes}M"));
+            },
+            When::Fixed(start, minutes) => {
+                lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%SZ")));
+                lines.push(format!("DURATION:PT{minutes}M"));
+            },
+        }
+        lines.push(format!("SUMMARY:{}", escape(&item.summary)));
+        if let Some(description) = &item.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        lines.push(format!("END:{component}"));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|l| fold(l) + "\r\n").collect()
+}
+
+// Write the calendar, leaving the file untouched when nothing has changed so clients don't reload it
+// Returns whether the file was written
+pub fn write_ics(path: &Path, items: &[Item], stamp: DateTime<Utc>) -> Result<bool, String> {
+    let text = to_ics(items, stamp);
+    if fs::read_to_string(path).is_ok_and(|existing| existing == text) {
+        return Ok(false);
+    }
+    // Written to the side then renamed so a client never reads a half written file
+    let temp = path.with_extension("ics.tmp");
+    fs::write(&temp, text).map_err(|e| format!("Failed to write calendar {}: {}", temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| format!("Failed to write calendar {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes are continued on the next line after a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp() -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2025, 8, 3).unwrap().and_hms_opt(23, 0, 0).unwrap().and_utc()
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, 4).unwrap()
+    }
+
+    #[test]
+    fn test_timed_tasks_are_events_and_days_are_todos() {
+        let items = vec![
+            Item { id: "2".to_string(), summary: "Call, then email".to_string(), description: None,
+                when: When::Floating(day().and_hms_opt(9, 30, 0).unwrap(), 45) },
+            Item { id: "1".to_string(), summary: "Tidy desk".to_string(), description: None,
+                when: When::Day(day()) },
+        ];
+        let ics = to_ics(&items, stamp());
+        assert_eq!(ics, "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todoist-tracker//EN\r\nCALSCALE:GREGORIAN\r\n\
+            BEGIN:VTODO\r\nUID:1@todoist-tracker\r\nDTSTAMP:20250803T230000Z\r\n\
+            DTSTART;VALUE=DATE:20250804\r\nDUE;VALUE=DATE:20250805\r\nSUMMARY:Tidy desk\r\nEND:VTODO\r\n\
+            BEGIN:VEVENT\r\nUID:2@todoist-tracker\r\nDTSTAMP:20250803T230000Z\r\n\
+            DTSTART:20250804T093000\r\nDURATION:PT45M\r\nSUMMARY:Call\\, then email\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let line = "SUMMARY:".to_string() + &"a".repeat(100);
+        let folded = fold(&line);
+        assert_eq!(folded.split("\r\n ").map(|l| l.len()).collect::<Vec<usize>>(), vec![75, 33]);
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_unchanged_calendar_is_not_rewritten() {
+        let path = std::env::temp_dir().join(format!("todoist-tracker-export-{}.ics", std::process::id()));
+        let items = vec![Item { id: "1".to_string(), summary: "Tidy desk".to_string(), description: None, when: When::Day(day()) }];
+        assert_eq!(write_ics(&path, &items, stamp()), Ok(true));
+        assert_eq!(write_ics(&path, &items, stamp()), Ok(false));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,382 @@
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use ical::parser::ical::component::IcalEvent;
+use ical::property::Property;
+use ical::IcalParser;
+use std::collections::HashSet;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::recurrence::Rule;
+
+// A span of time taken by an event, in local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusyTime {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+// Read the busy times between the first and last days from an .ics file, or every .ics file in a directory,
+// with recurring events expanded and overlapping events merged
+pub fn load_busy_times(path: &Path, first: NaiveDate, last: NaiveDate) -> Result<Vec<BusyTime>, String> {
+    let (from, to) = (first.and_time(NaiveTime::MIN), last.checked_add_days(Days::new(1)).unwrap().and_time(NaiveTime::MIN));
+    let mut busy: Vec<BusyTime> = Vec::new();
+    for file in ics_files(path)? {
+        let reader = BufReader::new(fs::File::open(&file)
+            .map_err(|e| format!("Failed to open calendar {}: {}", file.display(), e))?);
+        for calendar in IcalParser::new(reader) {
+            let calendar = calendar.map_err(|e| format!("Failed to parse calendar {}: {}", file.display(), e))?;
+            let events = calendar_busy_times(&calendar.events, last);
+            busy.extend(events.into_iter().filter(|b| b.start < to && b.end > from));
+        }
+    }
+    Ok(merge(busy))
+}
+
+fn ics_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read the calendar directory {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("ics")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// The busy times of a calendar's events up to the last day
+fn calendar_busy_times(events: &[IcalEvent], last: NaiveDate) -> Vec<BusyTime> {
+    // Occurrences moved or changed on their own are separate events, so they replace the occurrence from the rule
+    let overridden: HashSet<(String, NaiveDateTime)> = events.iter()
+        .filter_map(|e| {
+            let uid = property(e, "UID")?.value.clone()?;
+            let (start, zone) = parse_date_time(property(e, "RECURRENCE-ID")?)?;
+            Some((uid, zone.local(start)?))
+        })
+        .collect();
+    let mut busy: Vec<BusyTime> = Vec::new();
+    for event in events.iter() {
+        busy.extend(busy_times(event, last, &overridden));
+    }
+    busy
+}
+
+// Every time an event takes up to the last day, empty for events that don't block time
+// A recurrence that isn't supported is warned about and only its first occurrence counted, so one event can't stop the rest
+fn busy_times(event: &IcalEvent, last: NaiveDate, overridden: &HashSet<(String, NaiveDateTime)>) -> Vec<BusyTime> {
+    let value = |name: &str| property(event, name).and_then(|p| p.value.as_deref());
+    if value("TRANSP") == Some("TRANSPARENT") || value("STATUS") == Some("CANCELLED") {
+        return Vec::new();
+    }
+    let Some((start, zone)) = property(event, "DTSTART").and_then(parse_date_time) else {
+        return Vec::new();
+    };
+    let name = value("SUMMARY").or(value("UID")).unwrap_or_default();
+    let end = match property(event, "DTEND") {
+        // The end is put in the start's timezone so each occurrence keeps the same wall clock length
+        Some(p) => parse_date_time(p).and_then(|(end, end_zone)| zone.in_zone(end_zone.local(end)?)),
+        // All day events without an end take the whole day
+        None if zone == Zone::Date => start.checked_add_days(Days::new(1)),
+        None => Some(start + value("DURATION").and_then(parse_duration).unwrap_or_default()),
+    };
+    let Some(length) = end.map(|end| end - start).filter(|l| *l > TimeDelta::zero()) else {
+        log::debug!("Skipping event without any length at {start}");
+        return Vec::new();
+    };
+
+    // Recurring events are expanded in their own timezone so they follow its daylight saving changes
+    let mut starts: Vec<NaiveDateTime> = match value("RRULE").map(|rule| Rule::parse(rule, |until| parse_until(until, zone))) {
+        Some(Ok(rule)) => rule.occurrences(start, last.checked_add_days(Days::new(1)).unwrap()),
+        Some(Err(e)) => {
+            log::warn!("Only counting the first occurrence of {name} at {start}: {e}");
+            vec![start]
+        },
+        None => vec![start],
+    };
+    for p in properties(event, "RDATE") {
+        if param(p, "VALUE") == Some("PERIOD") {
+            log::warn!("Skipping the unsupported RDATE period {period} of {name}", period = p.value.as_deref().unwrap_or_default());
+            continue;
+        }
+        for (rdate, rdate_zone) in parse_date_times(p) {
+            starts.extend(rdate_zone.local(rdate).and_then(|l| zone.in_zone(l)));
+        }
+    }
+    let mut excluded: HashSet<NaiveDateTime> = properties(event, "EXDATE")
+        .flat_map(parse_date_times)
+        .filter_map(|(d, z)| z.local(d))
+        .collect();
+    if value("RECURRENCE-ID").is_none() {
+        if let Some(uid) = value("UID") {
+            excluded.extend(overridden.iter().filter(|(u, _)| u == uid).map(|(_, s)| *s));
+        }
+    }
+
+    let mut busy: Vec<BusyTime> = Vec::new();
+    for s in starts.into_iter() {
+        let (Some(local_start), Some(local_end)) = (zone.local(s), zone.local(s + length)) else {
+            continue;
+        };
+        if !excluded.contains(&local_start) {
+            busy.push(BusyTime { start: local_start, end: local_end });
+        }
+    }
+    busy
+}
+
+fn property<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a Property> {
+    properties(event, name).next()
+}
+
+fn properties<'a>(event: &'a IcalEvent, name: &str) -> impl Iterator<Item = &'a Property> {
+    let name = name.to_string();
+    event.properties.iter().filter(move |p| p.name == name)
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property.params.as_ref()?
+        .iter()
+        .find(|(n, _)| n == name)
+        .and_then(|(_, values)| values.first())
+        .map(|v| v.as_str())
+}
+
+// The time a value is in, dates are whole days and anything without a timezone is already local
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Date,
+    Floating,
+    Utc,
+    Named(Tz),
+}
+
+impl Zone {
+    fn local(self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Zone::Date | Zone::Floating => Some(time),
+            Zone::Utc => Some(DateTime::<Utc>::from_naive_utc_and_offset(time, Utc).with_timezone(&Local).naive_local()),
+            Zone::Named(tz) => tz.from_local_datetime(&time).earliest().map(|d| d.with_timezone(&Local).naive_local()),
+        }
+    }
+
+    fn in_zone(self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Zone::Date | Zone::Floating => Some(time),
+            Zone::Utc => Local.from_local_datetime(&time).earliest().map(|d| d.naive_utc()),
+            Zone::Named(tz) => Local.from_local_datetime(&time).earliest().map(|d| d.with_timezone(&tz).naive_local()),
+        }
+    }
+}
+
+fn parse_date_time(property: &Property) -> Option<(NaiveDateTime, Zone)> {
+    parse_date_times(property).into_iter().next()
+}
+
+// Each of the comma separated values, in the time they are given in
+fn parse_date_times(property: &Property) -> Vec<(NaiveDateTime, Zone)> {
+    let tz = param(property, "TZID").and_then(|tz| tz.parse::<Tz>().ok());
+    property.value.as_deref().unwrap_or_default()
+        .split(',')
+        .filter_map(|value| {
+            let value = value.trim();
+            if param(property, "VALUE") == Some("DATE") || value.len() == 8 {
+                return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|d| (d.and_time(NaiveTime::MIN), Zone::Date));
+            }
+            if let Some(utc) = value.strip_suffix('Z') {
+                return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok().map(|d| (d, Zone::Utc));
+            }
+            let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+            Some((naive, tz.map(Zone::Named).unwrap_or(Zone::Floating)))
+        })
+        .collect()
+}
+
+// The end of a recurrence rule in the same time as the event's start, an UNTIL date includes the whole day
+fn parse_until(value: &str, zone: Zone) -> Option<NaiveDateTime> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return zone.in_zone(Zone::Utc.local(naive)?);
+    }
+    if value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok()?.and_hms_opt(23, 59, 59);
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+}
+
+// Only the day and time parts of a duration are supported, e.g. PT1H30M or P1D
+fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let value = value.strip_prefix('P')?;
+    let (days, time) = value.split_once('T').unwrap_or((value, ""));
+    let mut total = TimeDelta::zero();
+    let date_units: &[(char, i64)] = &[('W', 7 * 24 * 60 * 60), ('D', 24 * 60 * 60)];
+    let time_units: &[(char, i64)] = &[('H', 60 * 60), ('M', 60), ('S', 1)];
+    for (part, units) in [(days, date_units), (time, time_units)] {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let seconds = units.iter().find(|(u, _)| *u == c)?.1;
+            total += TimeDelta::seconds(number.parse::<i64>().ok()? * seconds);
+            number.clear();
+        }
+    }
+    Some(total)
+}
+
+fn merge(mut busy: Vec<BusyTime>) -> Vec<BusyTime> {
+    busy.sort_by_key(|b| b.start);
+    let mut merged: Vec<BusyTime> = Vec::new();
+    for b in busy.into_iter() {
+        match merged.last_mut() {
+            Some(last) if b.start <= last.end => last.end = last.end.max(b.end),
+            _ => merged.push(b),
+        }
+    }
+    merged
+}
+
+// The minutes of a day's window taken by the busy times
+pub fn busy_minutes(busy: &[BusyTime], day: NaiveDate, window_start: NaiveTime, window_end: NaiveTime) -> i32 {
+    let (from, to) = (day.and_time(window_start), day.and_time(window_end));
+    busy.iter()
+        .map(|b| (b.start.max(from), b.end.min(to)))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| (end - start).num_minutes() as i32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(ics: &str) -> Vec<BusyTime> {
+        let calendar = IcalParser::new(BufReader::new(ics.as_bytes())).next().unwrap().unwrap();
+        merge(calendar_busy_times(&calendar.events, NaiveDate::from_ymd_opt(2025, 8, 31).unwrap()))
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+DTSTART:20250804T090000\r
+DTEND:20250804T103000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20250804T100000\r
+DURATION:PT1H\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20250804T140000\r
+DTEND:20250804T150000\r
+TRANSP:TRANSPARENT\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20250805\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_events_are_merged_and_transparent_skipped() {
+        assert_eq!(events(CALENDAR), vec![
+            BusyTime { start: at(4, 9, 0), end: at(4, 11, 0) },
+            BusyTime { start: at(5, 0, 0), end: at(6, 0, 0) },
+        ]);
+    }
+
+    #[test]
+    fn test_busy_minutes_are_clipped_to_the_window() {
+        let busy = events(CALENDAR);
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 8, d).unwrap();
+        assert_eq!(busy_minutes(&busy, day(4), time(10), time(17)), 60);
+        assert_eq!(busy_minutes(&busy, day(5), time(9), time(17)), 480);
+        assert_eq!(busy_minutes(&busy, day(6), time(9), time(17)), 0);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT1H30M"), Some(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("P1DT2H"), Some(TimeDelta::hours(26)));
+        assert_eq!(parse_duration("P1W"), Some(TimeDelta::days(7)));
+        assert_eq!(parse_duration("1H"), None);
+    }
+
+    const RECURRING: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:standup\r
+DTSTART:20250804T090000\r
+DTEND:20250804T093000\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4\r
+EXDATE:20250806T090000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+RECURRENCE-ID:20250811T090000\r
+DTSTART:20250811T140000\r
+DTEND:20250811T143000\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_recurring_events_are_expanded() {
+        // The 6th is excluded and the 11th is moved to the afternoon
+        assert_eq!(events(RECURRING), vec![
+            BusyTime { start: at(4, 9, 0), end: at(4, 9, 30) },
+            BusyTime { start: at(11, 14, 0), end: at(11, 14, 30) },
+            BusyTime { start: at(13, 9, 0), end: at(13, 9, 30) },
+        ]);
+    }
+
+    const MIXED: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:review\r
+DTSTART:20250804T130000\r
+DTEND:20250804T140000\r
+RRULE:FREQ=MONTHLY;BYDAY=1MO\r
+RDATE;VALUE=PERIOD:20250805T130000/PT1H\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:gym\r
+DTSTART:20250805T070000\r
+DTEND:20250805T080000\r
+RRULE:FREQ=DAILY;COUNT=2\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_unsupported_recurrence_only_counts_its_start() {
+        let path = std::env::temp_dir().join(format!("todoist-tracker-import-{}.ics", std::process::id()));
+        fs::write(&path, MIXED).unwrap();
+        let busy = load_busy_times(&path, NaiveDate::from_ymd_opt(2025, 8, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 8, 31).unwrap());
+        fs::remove_file(&path).unwrap();
+        // The monthly rule and the period aren't supported but the other events still count
+        assert_eq!(busy, Ok(vec![
+            BusyTime { start: at(4, 13, 0), end: at(4, 14, 0) },
+            BusyTime { start: at(5, 7, 0), end: at(5, 8, 0) },
+            BusyTime { start: at(6, 7, 0), end: at(6, 8, 0) },
+        ]));
+    }
+
+    #[test]
+    fn test_unreadable_calendar_is_an_error() {
+        let path = std::env::temp_dir().join("todoist-tracker-missing.ics");
+        assert!(load_busy_times(&path, NaiveDate::from_ymd_opt(2025, 8, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 8, 31).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod ics_export;
+pub mod ics_import;
+mod recurrence;
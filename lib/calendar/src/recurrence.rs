@@ -0,0 +1,198 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Weekday};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// The supported subset of an RRULE, anything else is refused rather than counted wrongly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+    by_day: Vec<Weekday>,
+    week_start: Weekday,
+}
+
+impl Rule {
+    // Parse an RRULE value, UNTIL is read with parse_until so it can be put in the same time as the start
+    pub fn parse(value: &str, parse_until: impl Fn(&str) -> Option<NaiveDateTime>) -> Result<Rule, String> {
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+        let mut frequency = None;
+        for part in value.split(';').filter(|p| !p.is_empty()) {
+            let (name, v) = part.split_once('=').ok_or_else(|| format!("Invalid recurrence rule {value}"))?;
+            let invalid = || format!("Invalid {name} in recurrence rule {value}");
+            match name {
+                "FREQ" => frequency = Some(match v {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return Err(format!("Unsupported frequency {v} in recurrence rule {value}")),
+                }),
+                "INTERVAL" => rule.interval = v.parse().ok().filter(|i| *i > 0).ok_or_else(invalid)?,
+                "COUNT" => rule.count = Some(v.parse().map_err(|_| invalid())?),
+                "UNTIL" => rule.until = Some(parse_until(v).ok_or_else(invalid)?),
+                // Only plain weekdays are supported, not ones with an ordinal such as 2MO
+                "BYDAY" => rule.by_day = v.split(',').map(weekday).collect::<Option<Vec<Weekday>>>().ok_or_else(|| format!("Unsupported BYDAY in recurrence rule {value}"))?,
+                "WKST" => rule.week_start = weekday(v).ok_or_else(invalid)?,
+                _ => return Err(format!("Unsupported {name} in recurrence rule {value}")),
+            }
+        }
+        rule.frequency = frequency.ok_or_else(|| format!("Missing FREQ in recurrence rule {value}"))?;
+        if !rule.by_day.is_empty() && matches!(rule.frequency, Frequency::Monthly | Frequency::Yearly) {
+            return Err(format!("Unsupported BYDAY for a monthly or yearly recurrence rule {value}"));
+        }
+        Ok(rule)
+    }
+
+    // Every occurrence starting from the start up to and including the last day, the first occurrence is the start itself
+    pub fn occurrences(&self, start: NaiveDateTime, last: NaiveDate) -> Vec<NaiveDateTime> {
+        // The start is always the first occurrence, even when it doesn't match the rule
+        if start.date() > last {
+            return Vec::new();
+        }
+        let mut occurrences: Vec<NaiveDateTime> = vec![start];
+        let mut counted = 1;
+        for period in 0.. {
+            if self.period_start(start, period).is_none_or(|p| p > last) {
+                break;
+            }
+            let Some(candidates) = self.period(start, period) else {
+                break;
+            };
+            for c in candidates.into_iter().filter(|c| *c > start) {
+                if self.until.is_some_and(|u| c > u) || self.count.is_some_and(|n| counted >= n) || c.date() > last {
+                    return occurrences;
+                }
+                counted += 1;
+                occurrences.push(c);
+            }
+        }
+        occurrences
+    }
+
+    // The first day of a period, used to stop once the periods pass the last day even when they have no occurrences
+    fn period_start(&self, start: NaiveDateTime, period: u32) -> Option<NaiveDate> {
+        let steps = period.checked_mul(self.interval)?;
+        let date = start.date();
+        match self.frequency {
+            Frequency::Daily => date.checked_add_days(Days::new(steps as u64)),
+            Frequency::Weekly => self.week_of(date)?.checked_add_days(Days::new(steps as u64 * 7)),
+            Frequency::Monthly => date.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => date.with_day(1)?.with_month(1)?.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    // The occurrences in a period in order, days that don't exist in a period, such as the 31st, are skipped
+    fn period(&self, start: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let steps = period.checked_mul(self.interval)?;
+        let (date, time) = (start.date(), start.time());
+        let dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => {
+                let d = date.checked_add_days(Days::new(steps as u64))?;
+                if self.by_day.is_empty() || self.by_day.contains(&d.weekday()) { vec![d] } else { Vec::new() }
+            },
+            Frequency::Weekly => {
+                let week = self.week_of(date)?.checked_add_days(Days::new(steps as u64 * 7))?;
+                let days = if self.by_day.is_empty() { vec![date.weekday()] } else { self.by_day.clone() };
+                let mut dates: Vec<NaiveDate> = days.iter()
+                    .map(|d| (7 + d.num_days_from_monday() - self.week_start.num_days_from_monday()) % 7)
+                    .filter_map(|offset| week.checked_add_days(Days::new(offset as u64)))
+                    .collect();
+                dates.sort();
+                dates.dedup();
+                dates
+            },
+            Frequency::Monthly => {
+                let month = date.with_day(1)?.checked_add_months(Months::new(steps))?;
+                month.with_day(date.day()).into_iter().collect()
+            },
+            Frequency::Yearly => {
+                let year = date.year().checked_add(steps as i32)?;
+                NaiveDate::from_ymd_opt(year, date.month(), date.day()).into_iter().collect()
+            },
+        };
+        Some(dates.into_iter().map(|d| d.and_time(time)).collect())
+    }
+
+    // The first day of the week containing the date, from the rule's week start
+    fn week_of(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let back = (7 + date.weekday().num_days_from_monday() - self.week_start.num_days_from_monday()) % 7;
+        date.checked_sub_days(Days::new(back as u64))
+    }
+}
+
+fn weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn rule(value: &str) -> Rule {
+        Rule::parse(value, |v| NaiveDateTime::parse_from_str(v, "%Y%m%dT%H%M%S").ok()).unwrap()
+    }
+
+    fn last(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_weekly_on_several_days() {
+        // Monday the 4th of August
+        let occurrences = rule("FREQ=WEEKLY;BYDAY=MO,WE").occurrences(at(8, 4, 9), last(8, 13));
+        assert_eq!(occurrences, vec![at(8, 4, 9), at(8, 6, 9), at(8, 11, 9), at(8, 13, 9)]);
+    }
+
+    #[test]
+    fn test_count_and_until_end_the_rule() {
+        assert_eq!(rule("FREQ=DAILY;COUNT=3").occurrences(at(8, 4, 9), last(8, 30)), vec![at(8, 4, 9), at(8, 5, 9), at(8, 6, 9)]);
+        assert_eq!(rule("FREQ=DAILY;INTERVAL=2;UNTIL=20250808T090000").occurrences(at(8, 4, 9), last(8, 30)), vec![at(8, 4, 9), at(8, 6, 9), at(8, 8, 9)]);
+    }
+
+    #[test]
+    fn test_weekdays_only_daily() {
+        // Friday the 8th to Tuesday the 12th
+        assert_eq!(rule("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR").occurrences(at(8, 8, 9), last(8, 12)), vec![at(8, 8, 9), at(8, 11, 9), at(8, 12, 9)]);
+    }
+
+    #[test]
+    fn test_monthly_skips_missing_days() {
+        assert_eq!(rule("FREQ=MONTHLY").occurrences(at(1, 31, 9), last(5, 31)), vec![at(1, 31, 9), at(3, 31, 9), at(5, 31, 9)]);
+    }
+
+    #[test]
+    fn test_unsupported_rules_are_refused() {
+        let parse = |v: &str| Rule::parse(v, |_| None);
+        assert!(parse("FREQ=MONTHLY;BYDAY=2MO").is_err());
+        assert!(parse("FREQ=MONTHLY;BYMONTHDAY=1,15").is_err());
+        assert!(parse("FREQ=HOURLY").is_err());
+    }
+}
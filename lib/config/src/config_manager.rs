@@ -0,0 +1,185 @@
+use directories::ProjectDirs;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::settings::{self, Config};
+
+static CONFIG_NAME: &str = "config.toml";
+static PROFILES_DIR: &str = "profiles";
+static ENV_PREFIX: &str = "TODOIST_TRACKER_";
+
+fn config_dir() -> PathBuf {
+    let binding = ProjectDirs::from("com", "everest", "todoist_productivity_tracker")
+        .expect("Failed to get project directories");
+    binding.config_dir().to_path_buf()
+}
+
+// The location of the config file, in the operating system's standard config directory
+// The default profile uses config.toml and each named profile uses profiles/<name>.toml
+pub fn config_path(profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(p) => config_dir().join(PROFILES_DIR).join(format!("{p}.toml")),
+        None => config_dir().join(CONFIG_NAME),
+    }
+}
+
+// The named profiles, found from the files in the profiles directory
+pub fn list_profiles() -> Result<Vec<String>, String> {
+    let dir = config_dir().join(PROFILES_DIR);
+    if !fs::exists(&dir).map_err(|e| e.to_string())? {
+        return Ok(Vec::new());
+    }
+    let mut profiles: Vec<String> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read the profiles directory {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml"))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    profiles.sort();
+    Ok(profiles)
+}
+
+// Load the config, layering in order: defaults, the config file, environment variables, then command line overrides
+// Environment variables take the form TODOIST_TRACKER_<SECTION>__<KEY>, e.g. TODOIST_TRACKER_TASKS__LOW_PRIORITY=2
+// Command line overrides take the form <section>.<key>=<value>, e.g. tasks.low_priority=2
+pub fn load(profile: Option<&str>, file: Option<&Path>, overrides: &[String]) -> Result<Config, String> {
+    if let Some(p) = profile {
+        validate_profile_name(p)?;
+    }
+    let path = file.map(|p| p.to_path_buf()).unwrap_or_else(|| config_path(profile));
+    // A named profile must exist, otherwise a typo would silently use the defaults
+    let mut table = read_file(&path, file.is_some() || profile.is_some())?;
+
+    // Named profiles keep their own database unless the file says otherwise
+    if let Some(p) = profile {
+        let db = table.entry("db").or_insert_with(|| Value::Table(Table::new()));
+        if let Some(db) = db.as_table_mut() {
+            db.entry("database").or_insert_with(|| Value::String(settings::profile_database_name(p)));
+        }
+    }
+
+    for (key, value) in env_overrides(env::vars()) {
+        apply_override(&mut table, &key, &value)?;
+    }
+    for o in overrides.iter() {
+        let (key, value) = o.split_once('=')
+            .ok_or(format!("Config override {o} must be in the form <section>.<key>=<value>"))?;
+        apply_override(&mut table, key.trim(), value.trim())?;
+    }
+
+    let config: Config = Value::Table(table).try_into()
+        .map_err(|e: toml::de::Error| format!("Invalid config: {}", e.message()))?;
+    log::trace!("Loaded config from {}", path.display());
+    Ok(config)
+}
+
+fn validate_profile_name(profile: &str) -> Result<(), String> {
+    if profile.is_empty() || !profile.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Profile name {profile} must only contain letters, numbers, - and _"));
+    }
+    Ok(())
+}
+
+// A missing file is only an error if it was asked for explicitly
+fn read_file(path: &Path, required: bool) -> Result<Table, String> {
+    if !fs::exists(path).map_err(|e| e.to_string())? {
+        if required {
+            return Err(format!("Config file {} does not exist", path.display()));
+        }
+        log::debug!("No config file at {}, using defaults", path.display());
+        return Ok(Table::new());
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    contents.parse::<Table>()
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e.message()))
+}
+
+fn env_overrides(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let mut found: Vec<(String, String)> = vars
+        .filter_map(|(k, v)| k.strip_prefix(ENV_PREFIX).map(|s| (s.to_lowercase().replace("__", "."), v)))
+        .collect();
+    // Sort to make the order of application stable
+    found.sort();
+    found
+}
+
+fn apply_override(table: &mut Table, key: &str, raw: &str) -> Result<(), String> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("Config key {key} is not valid"));
+    }
+    let (last, sections) = parts.split_last().unwrap();
+    let mut current = table;
+    for s in sections.iter() {
+        let entry = current.entry(s.to_string()).or_insert_with(|| Value::Table(Table::new()));
+        current = entry.as_table_mut().ok_or(format!("Config key {key} is not a section"))?;
+    }
+    current.insert(last.to_string(), parse_value(raw));
+    Ok(())
+}
+
+// Anything that is not a valid TOML value is treated as a plain string
+fn parse_value(raw: &str) -> Value {
+    format!("value = {raw}").parse::<Table>()
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value_types() {
+        assert_eq!(parse_value("2"), Value::Integer(2));
+        assert_eq!(parse_value("true"), Value::Boolean(true));
+        assert_eq!(parse_value("\"quoted\""), Value::String("quoted".to_string()));
+        assert_eq!(parse_value("%d/%m/%Y"), Value::String("%d/%m/%Y".to_string()));
+    }
+
+    #[test]
+    fn test_apply_override_creates_sections() {
+        let mut table = Table::new();
+        apply_override(&mut table, "tasks.low_priority", "2").unwrap();
+        let config: Config = Value::Table(table).try_into().unwrap();
+        assert_eq!(config.tasks.low_priority, 2);
+        assert_eq!(config.goals.days_per_week, 7);
+    }
+
+    #[test]
+    fn test_later_layers_win() {
+        let mut table: Table = "[tasks]\nlow_priority = 2".parse().unwrap();
+        apply_override(&mut table, "tasks.low_priority", "3").unwrap();
+        let config: Config = Value::Table(table).try_into().unwrap();
+        assert_eq!(config.tasks.low_priority, 3);
+    }
+
+    #[test]
+    fn test_env_overrides_are_mapped_to_keys() {
+        let vars = vec![
+            ("TODOIST_API_KEY".to_string(), "secret".to_string()),
+            ("TODOIST_TRACKER_TASKS__LOW_PRIORITY".to_string(), "2".to_string()),
+        ];
+        assert_eq!(env_overrides(vars.into_iter()), vec![("tasks.low_priority".to_string(), "2".to_string())]);
+    }
+
+    #[test]
+    fn test_profile_names_are_restricted() {
+        assert!(validate_profile_name("work").is_ok());
+        assert!(validate_profile_name("side-project_2").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../work").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let mut table = Table::new();
+        apply_override(&mut table, "tasks.unknown", "2").unwrap();
+        assert!(Value::Table(table).try_into::<Config>().is_err());
+    }
+}
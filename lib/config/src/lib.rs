@@ -0,0 +1,2 @@
+pub mod config_manager;
+pub mod settings;
\ No newline at end of file
@@ -0,0 +1,894 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub static DATABASE_NAME: &str = "todoist_productivity_tracker_database.db";
+
+// The full configuration, every section falls back to its defaults when missing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub api: ApiConfig,
+    pub token: TokenConfig,
+    pub db: DbConfig,
+    pub day: DayConfig,
+    pub week: WeekConfig,
+    pub schedule: ScheduleConfig,
+    pub tasks: TasksConfig,
+    pub recurring: RecurringConfig,
+    pub overdue: OverdueConfig,
+    pub postpone: PostponeConfig,
+    pub rebalance: RebalanceConfig,
+    pub triage: TriageConfig,
+    pub priorities: PrioritiesConfig,
+    pub protect: ProtectConfig,
+    pub capacity: CapacityConfig,
+    pub wip: WipConfig,
+    pub time_block: TimeBlockConfig,
+    pub calendar: CalendarConfig,
+    pub escalation: EscalationConfig,
+    pub goals: GoalsConfig,
+    pub formats: FormatsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// The base of every Todoist API request
+    pub base_url: String,
+    /// The maximum number of tasks returned by a single filter request
+    pub filter_limit: i32,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            base_url: "https://api.todoist.com/api/v1".to_string(),
+            filter_limit: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSource {
+    Env,
+    File,
+    Command,
+    Store,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    /// Where the API token is read from: env, file, command or store
+    pub source: TokenSource,
+    /// The environment variable holding the token for the env source
+    pub env_var: String,
+    /// The file holding the token for the file source, it must only be accessible by its owner
+    pub file: Option<PathBuf>,
+    /// The command run for the command source, the token is read from its stdout
+    pub command: Option<String>,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        TokenConfig {
+            source: TokenSource::Env,
+            env_var: "TODOIST_API_KEY".to_string(),
+            file: None,
+            command: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbConfig {
+    /// The database file in the data directory, named profiles default to their own file
+    pub database: String,
+    /// Number of days an excluded day is kept for
+    pub excluded_days_retention: u64,
+    /// Number of days an excluded week is kept for, measured from the start of the week
+    pub excluded_weeks_retention: u64,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        DbConfig {
+            database: DATABASE_NAME.to_string(),
+            excluded_days_retention: 7,
+            excluded_weeks_retention: 35,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DayConfig {
+    /// The time the day starts, in the format HH:MM, anything earlier still counts as the previous day
+    pub starts_at: String,
+}
+
+impl Default for DayConfig {
+    fn default() -> Self {
+        DayConfig {
+            starts_at: "00:00".to_string(),
+        }
+    }
+}
+
+impl DayConfig {
+    pub fn start_time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(&self.starts_at, "%H:%M").ok()
+    }
+
+    // The day a moment belongs to once the day start is taken into account
+    pub fn day_of(&self, moment: NaiveDateTime) -> NaiveDate {
+        let start = self.start_time().unwrap_or(NaiveTime::MIN);
+        if moment.time() < start {
+            moment.date().pred_opt().unwrap()
+        }
+        else {
+            moment.date()
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.day_of(Local::now().naive_local())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    /// Use the start day from the Todoist user settings
+    Auto,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl WeekStart {
+    // The configured weekday, None when it should be read from Todoist
+    pub fn weekday(&self) -> Option<Weekday> {
+        match self {
+            WeekStart::Auto => None,
+            WeekStart::Monday => Some(Weekday::Mon),
+            WeekStart::Tuesday => Some(Weekday::Tue),
+            WeekStart::Wednesday => Some(Weekday::Wed),
+            WeekStart::Thursday => Some(Weekday::Thu),
+            WeekStart::Friday => Some(Weekday::Fri),
+            WeekStart::Saturday => Some(Weekday::Sat),
+            WeekStart::Sunday => Some(Weekday::Sun),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeekConfig {
+    /// The first day of the week, auto reads it from the Todoist user settings
+    pub start_day: WeekStart,
+}
+
+impl Default for WeekConfig {
+    fn default() -> Self {
+        WeekConfig {
+            start_day: WeekStart::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    /// Weekdays tasks are never postponed onto, e.g. ["saturday", "sunday"]
+    pub non_working_weekdays: Vec<String>,
+    /// Days excluded every year, in the format MM-DD, e.g. ["12-25"]
+    pub recurring_exclusions: Vec<String>,
+}
+
+impl ScheduleConfig {
+    pub fn non_working_weekdays(&self) -> Vec<Weekday> {
+        self.non_working_weekdays.iter().filter_map(|d| d.parse().ok()).collect()
+    }
+
+    // Each exclusion as a (month, day) pair
+    pub fn recurring_exclusions(&self) -> Vec<(u32, u32)> {
+        self.recurring_exclusions.iter().filter_map(|d| parse_month_day(d)).collect()
+    }
+}
+
+fn parse_month_day(value: &str) -> Option<(u32, u32)> {
+    let (month, day) = value.split_once('-')?;
+    let (month, day): (u32, u32) = (month.parse().ok()?, day.parse().ok()?);
+    // A leap year so 02-29 is allowed
+    NaiveDate::from_ymd_opt(2024, month, day).map(|_| (month, day))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TasksConfig {
+    /// The API priority treated as low priority and so can be moved, 1 is the lowest (p4 in the app)
+    pub low_priority: i32,
+    /// Count a task and its subtasks as one task toward the goal based limits of --postpone-to-goal
+    pub count_tree_once: bool,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        TasksConfig {
+            low_priority: 1,
+            count_tree_once: false,
+        }
+    }
+}
+
+// How a recurring task is handled when it would be moved
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurringPolicy {
+    /// Move only this occurrence, keeping the recurrence rule
+    Occurrence,
+    /// Move on to the next occurrence without completing this one
+    Skip,
+    /// Don't move recurring tasks
+    Leave,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecurringConfig {
+    /// The policy for --postpone
+    pub postpone: RecurringPolicy,
+    /// The policy for --postpone-to-goal
+    pub postpone_to_goal: RecurringPolicy,
+    /// The policy for --postpone-by-days
+    pub postpone_by_days: RecurringPolicy,
+    /// The policy for --overdue and the overdue tasks moved by --postpone-to-goal
+    pub overdue: RecurringPolicy,
+    /// The policy for the rebalance command
+    pub rebalance: RecurringPolicy,
+    /// The policy for the balance-priorities command
+    pub balance_priorities: RecurringPolicy,
+}
+
+impl Default for RecurringConfig {
+    fn default() -> Self {
+        RecurringConfig {
+            postpone: RecurringPolicy::Occurrence,
+            postpone_to_goal: RecurringPolicy::Occurrence,
+            postpone_by_days: RecurringPolicy::Occurrence,
+            overdue: RecurringPolicy::Occurrence,
+            rebalance: RecurringPolicy::Occurrence,
+            balance_priorities: RecurringPolicy::Occurrence,
+        }
+    }
+}
+
+// How overdue tasks are brought back into the schedule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverduePolicy {
+    /// Move every overdue task to today
+    Today,
+    /// Spread them over today and the following working days, up to a daily cap
+    Spread,
+    /// Move each to the next day on the same weekday it was due
+    SameWeekday,
+    /// Move recently overdue tasks to today and label the rest for triage
+    Triage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverdueConfig {
+    /// How overdue tasks are moved: today, spread, same_weekday or triage
+    pub policy: OverduePolicy,
+    /// The number of days the spread policy uses, starting with today
+    pub spread_days: u64,
+    /// The most tasks the spread policy fills a day up to
+    pub max_tasks_per_day: i32,
+    /// Tasks overdue by at least this many days are labelled by the triage policy instead of moved
+    pub triage_after_days: i64,
+    /// The label the triage policy adds
+    pub triage_label: String,
+}
+
+impl Default for OverdueConfig {
+    fn default() -> Self {
+        OverdueConfig {
+            policy: OverduePolicy::Today,
+            spread_days: 3,
+            max_tasks_per_day: 5,
+            triage_after_days: 7,
+            triage_label: "triage".to_string(),
+        }
+    }
+}
+
+// How a postponement that would pass a task's deadline is handled
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadlinePolicy {
+    /// Move the task to its deadline instead
+    Cap,
+    /// Leave the task where it is
+    Refuse,
+}
+
+// A way of ordering the tasks that can be postponed, so the ones cared about least move first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostponeOrder {
+    /// Tasks at the bottom of the day's list first
+    DayOrder,
+    /// Tasks added longest ago first
+    Oldest,
+    /// Tasks added most recently first
+    Newest,
+    /// Tasks the tracker has postponed the most first
+    MostPostponed,
+    /// Tasks in the projects listed in project_order first, in that order
+    Project,
+    /// Tasks with the labels listed in label_order first, in that order
+    Label,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostponeConfig {
+    /// The number of upcoming working days --postpone-to-goal spreads tasks across
+    pub horizon_days: u64,
+    /// The most tasks --postpone-to-goal fills a day up to, defaults to the daily cap from the weekly goal
+    pub max_tasks_per_day: Option<i32>,
+    /// The orderings applied to the tasks that can be postponed, later ones break ties, empty keeps the API order
+    pub order: Vec<PostponeOrder>,
+    /// Project ids used by the project ordering, the first moves first
+    pub project_order: Vec<String>,
+    /// Label names used by the label ordering, the first moves first
+    pub label_order: Vec<String>,
+    /// What to do when a postponement would pass the task's deadline
+    pub deadline: DeadlinePolicy,
+}
+
+impl Default for PostponeConfig {
+    fn default() -> Self {
+        PostponeConfig {
+            horizon_days: 7,
+            max_tasks_per_day: None,
+            order: Vec::new(),
+            project_order: Vec::new(),
+            label_order: Vec::new(),
+            deadline: DeadlinePolicy::Cap,
+        }
+    }
+}
+
+// What the rebalance command evens out across the upcoming days
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RebalanceBy {
+    /// The number of tasks, toward the daily cap from the weekly goal
+    Count,
+    /// The task durations, toward the minutes available from the capacity config
+    Minutes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RebalanceConfig {
+    /// The number of days after today that are rebalanced
+    pub days: u64,
+    pub by: RebalanceBy,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        RebalanceConfig {
+            days: 7,
+            by: RebalanceBy::Count,
+        }
+    }
+}
+
+// Where the triage command takes undated tasks from and the days it can give them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TriageConfig {
+    /// The Todoist filter for the tasks to schedule, only tasks without a due date are used
+    pub filter: String,
+    /// The number of working days, starting with today, that tasks are scheduled onto
+    pub days: u64,
+}
+
+impl Default for TriageConfig {
+    fn default() -> Self {
+        TriageConfig {
+            filter: "#Inbox & no date".to_string(),
+            days: 7,
+        }
+    }
+}
+
+// The most high priority tasks allowed on a day, checked by the balance-priorities command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrioritiesConfig {
+    /// The most tasks of each priority on a day, keyed by the app's names, e.g. { p1 = 2, p2 = 4 }
+    pub limits: BTreeMap<String, i32>,
+    /// The number of working days, starting with today, that are checked and moved onto
+    pub days: u64,
+}
+
+impl Default for PrioritiesConfig {
+    fn default() -> Self {
+        PrioritiesConfig {
+            limits: BTreeMap::new(),
+            days: 7,
+        }
+    }
+}
+
+impl PrioritiesConfig {
+    // Each limit as the API priority, where 4 is p1, and the most tasks allowed
+    pub fn limits(&self) -> Vec<(i32, i32)> {
+        self.limits.iter()
+            .filter_map(|(p, max)| api_priority(p).map(|p| (p, *max)))
+            .collect()
+    }
+}
+
+fn api_priority(name: &str) -> Option<i32> {
+    match name {
+        "p1" => Some(4),
+        "p2" => Some(3),
+        "p3" => Some(2),
+        "p4" => Some(1),
+        _ => None,
+    }
+}
+
+// Tasks matching any of these rules are never postponed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtectConfig {
+    /// Labels that protect a task, e.g. fixed
+    pub labels: Vec<String>,
+    /// Project ids whose tasks are protected
+    pub projects: Vec<String>,
+    /// Section ids whose tasks are protected
+    pub sections: Vec<String>,
+    /// Whether tasks with a deadline are protected
+    pub deadline: bool,
+    /// Regular expressions matched against the task content
+    pub content_patterns: Vec<String>,
+}
+
+// The time available for tasks each day, used by the plan command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CapacityConfig {
+    /// The minutes available for tasks on a day
+    pub daily_minutes: i32,
+    /// The minutes available on particular weekdays, overriding daily_minutes, e.g. { saturday = 120 }
+    pub weekday_minutes: BTreeMap<String, i32>,
+    /// The minutes assumed for a task without a duration
+    pub default_task_minutes: i32,
+}
+
+impl Default for CapacityConfig {
+    fn default() -> Self {
+        CapacityConfig {
+            daily_minutes: 480,
+            weekday_minutes: BTreeMap::new(),
+            default_task_minutes: 0,
+        }
+    }
+}
+
+impl CapacityConfig {
+    // The minutes available on a day
+    pub fn minutes_on(&self, day: NaiveDate) -> i32 {
+        self.weekday_minutes.iter()
+            .find(|(d, _)| d.parse::<Weekday>().is_ok_and(|w| w == day.weekday()))
+            .map(|(_, m)| *m)
+            .unwrap_or(self.daily_minutes)
+    }
+}
+
+// A hard limit on what is due today, whatever the goals need
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WipConfig {
+    /// The most tasks due today, unset for no limit
+    pub max_tasks: Option<i32>,
+    /// The most minutes of tasks due today, using the capacity default for tasks without a duration, unset for no limit
+    pub max_minutes: Option<i32>,
+}
+
+impl WipConfig {
+    pub fn is_limited(&self) -> bool {
+        self.max_tasks.is_some() || self.max_minutes.is_some()
+    }
+}
+
+// The working window the time-block command packs today's tasks into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeBlockConfig {
+    /// The time the working window starts, in the format HH:MM
+    pub start: String,
+    /// The time the working window ends, in the format HH:MM
+    pub end: String,
+    /// Times kept free, each in the format HH:MM-HH:MM, e.g. ["12:00-13:00"]
+    pub breaks: Vec<String>,
+    /// The minutes blocked out for a task without a duration
+    pub default_minutes: i32,
+}
+
+impl Default for TimeBlockConfig {
+    fn default() -> Self {
+        TimeBlockConfig {
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+            breaks: Vec::new(),
+            default_minutes: 30,
+        }
+    }
+}
+
+impl TimeBlockConfig {
+    // The start and end of the working window, None if either is invalid
+    pub fn window(&self) -> Option<(NaiveTime, NaiveTime)> {
+        Some((parse_time(&self.start)?, parse_time(&self.end)?))
+    }
+
+    pub fn breaks(&self) -> Vec<(NaiveTime, NaiveTime)> {
+        self.breaks.iter().filter_map(|b| parse_time_range(b)).collect()
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+fn parse_time_range(value: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = value.split_once('-')?;
+    let (start, end) = (parse_time(start)?, parse_time(end)?);
+    (start < end).then_some((start, end))
+}
+
+// A calendar whose events make days busy, lowering what is expected on them, and one the schedule is exported to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// An .ics file, or a directory of them, busy time is counted within the time_block working window
+    pub busy_path: Option<PathBuf>,
+    /// Exclude days whose working window is fully booked from the goal calculation
+    pub exclude_full_days: bool,
+    /// The .ics file today's and upcoming tasks are exported to, also refreshed after --postpone-to-goal
+    pub export_path: Option<PathBuf>,
+    /// The number of days after today to export
+    pub export_days: u64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            busy_path: None,
+            exclude_full_days: false,
+            export_path: None,
+            export_days: 7,
+        }
+    }
+}
+
+// What happens to a task once it has been postponed too many times
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EscalationConfig {
+    /// The number of postpones that triggers escalation, unset turns it off
+    pub after: Option<i32>,
+    /// Raise the priority by one level
+    pub raise_priority: bool,
+    /// A label to add to the task
+    pub add_label: Option<String>,
+    /// The id of a project to move the task to, e.g. a Someday project
+    pub move_to_project: Option<String>,
+    /// A comment to add to the task, {count} is replaced with the number of postpones
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GoalsConfig {
+    /// The number of days the weekly goal is spread across, used to cap the tasks needed on a single day
+    pub days_per_week: i32,
+}
+
+impl Default for GoalsConfig {
+    fn default() -> Self {
+        GoalsConfig {
+            days_per_week: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FormatsConfig {
+    /// The format of dates given on the command line and printed in output
+    pub date: String,
+}
+
+impl Default for FormatsConfig {
+    fn default() -> Self {
+        FormatsConfig {
+            date: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+// The database used by a named profile, kept separate so exclusions and history don't mix
+pub fn profile_database_name(profile: &str) -> String {
+    format!("todoist_productivity_tracker_database_{profile}.db")
+}
+
+impl Config {
+    // The daily cap needed to meet the weekly goal, used to avoid over clogging days
+    pub fn daily_cap(&self, weekly_goal: i32) -> i32 {
+        weekly_goal / self.goals.days_per_week
+    }
+
+    // Returns every problem found rather than stopping at the first one
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors: Vec<String> = Vec::new();
+        if !self.api.base_url.starts_with("http://") && !self.api.base_url.starts_with("https://") {
+            errors.push(format!("api.base_url must be a http(s) url, found {}", self.api.base_url));
+        }
+        if self.api.filter_limit < 1 || self.api.filter_limit > 200 {
+            errors.push(format!("api.filter_limit must be between 1 and 200, found {}", self.api.filter_limit));
+        }
+        if self.token.source == TokenSource::Env && self.token.env_var.is_empty() {
+            errors.push("token.env_var must be set for the env token source".to_string());
+        }
+        if self.token.source == TokenSource::File && self.token.file.is_none() {
+            errors.push("token.file must be set for the file token source".to_string());
+        }
+        if self.token.source == TokenSource::Command && self.token.command.as_deref().is_none_or(|c| c.trim().is_empty()) {
+            errors.push("token.command must be set for the command token source".to_string());
+        }
+        if self.db.database.is_empty() || self.db.database.contains(['/', '\\']) {
+            errors.push(format!("db.database must be a file name, found {}", self.db.database));
+        }
+        if self.db.excluded_days_retention < 7 {
+            errors.push(format!("db.excluded_days_retention must be at least 7 to cover the floating week, found {}", self.db.excluded_days_retention));
+        }
+        if self.db.excluded_weeks_retention < 7 {
+            errors.push(format!("db.excluded_weeks_retention must be at least 7, found {}", self.db.excluded_weeks_retention));
+        }
+        if self.day.start_time().is_none() {
+            errors.push(format!("day.starts_at must be a time in the format HH:MM, found {}", self.day.starts_at));
+        }
+        for d in self.schedule.non_working_weekdays.iter() {
+            if d.parse::<Weekday>().is_err() {
+                errors.push(format!("schedule.non_working_weekdays must only contain day names, found {d}"));
+            }
+        }
+        if self.schedule.non_working_weekdays().len() >= 7 {
+            errors.push("schedule.non_working_weekdays must leave at least one working day".to_string());
+        }
+        for d in self.schedule.recurring_exclusions.iter() {
+            if parse_month_day(d).is_none() {
+                errors.push(format!("schedule.recurring_exclusions must be in the format MM-DD, found {d}"));
+            }
+        }
+        if self.tasks.low_priority < 1 || self.tasks.low_priority > 4 {
+            errors.push(format!("tasks.low_priority must be between 1 and 4, found {}", self.tasks.low_priority));
+        }
+        if self.overdue.spread_days < 1 || self.overdue.spread_days > 30 {
+            errors.push(format!("overdue.spread_days must be between 1 and 30, found {}", self.overdue.spread_days));
+        }
+        if self.overdue.max_tasks_per_day < 1 {
+            errors.push(format!("overdue.max_tasks_per_day must be at least 1, found {}", self.overdue.max_tasks_per_day));
+        }
+        if self.overdue.triage_after_days < 1 {
+            errors.push(format!("overdue.triage_after_days must be at least 1, found {}", self.overdue.triage_after_days));
+        }
+        if self.overdue.triage_label.trim().is_empty() {
+            errors.push("overdue.triage_label must be set".to_string());
+        }
+        if self.postpone.horizon_days < 1 || self.postpone.horizon_days > 30 {
+            errors.push(format!("postpone.horizon_days must be between 1 and 30, found {}", self.postpone.horizon_days));
+        }
+        if self.postpone.max_tasks_per_day.is_some_and(|m| m < 1) {
+            errors.push("postpone.max_tasks_per_day must be at least 1".to_string());
+        }
+        if self.postpone.order.contains(&PostponeOrder::Project) && self.postpone.project_order.is_empty() {
+            errors.push("postpone.project_order must be set to order by project".to_string());
+        }
+        if self.postpone.order.contains(&PostponeOrder::Label) && self.postpone.label_order.is_empty() {
+            errors.push("postpone.label_order must be set to order by label".to_string());
+        }
+        if self.rebalance.days < 2 || self.rebalance.days > 30 {
+            errors.push(format!("rebalance.days must be between 2 and 30, found {}", self.rebalance.days));
+        }
+        if self.triage.filter.trim().is_empty() {
+            errors.push("triage.filter must be set".to_string());
+        }
+        if self.triage.days < 1 || self.triage.days > 30 {
+            errors.push(format!("triage.days must be between 1 and 30, found {}", self.triage.days));
+        }
+        for (p, max) in self.priorities.limits.iter() {
+            if api_priority(p).is_none() {
+                errors.push(format!("priorities.limits must only contain p1 to p4, found {p}"));
+            }
+            if *max < 0 {
+                errors.push(format!("priorities.limits must not be negative, found {max} for {p}"));
+            }
+        }
+        if self.priorities.days < 2 || self.priorities.days > 30 {
+            errors.push(format!("priorities.days must be between 2 and 30, found {}", self.priorities.days));
+        }
+        for p in self.protect.content_patterns.iter() {
+            if let Err(e) = Regex::new(p) {
+                errors.push(format!("protect.content_patterns must be regular expressions, {p} failed with {e}"));
+            }
+        }
+        if self.capacity.daily_minutes < 0 || self.capacity.weekday_minutes.values().any(|m| *m < 0) {
+            errors.push("capacity minutes must not be negative".to_string());
+        }
+        for d in self.capacity.weekday_minutes.keys() {
+            if d.parse::<Weekday>().is_err() {
+                errors.push(format!("capacity.weekday_minutes must only contain day names, found {d}"));
+            }
+        }
+        if self.capacity.default_task_minutes < 0 {
+            errors.push(format!("capacity.default_task_minutes must not be negative, found {}", self.capacity.default_task_minutes));
+        }
+        if self.wip.max_tasks.is_some_and(|m| m < 0) {
+            errors.push("wip.max_tasks must not be negative".to_string());
+        }
+        if self.wip.max_minutes.is_some_and(|m| m < 0) {
+            errors.push("wip.max_minutes must not be negative".to_string());
+        }
+        match self.time_block.window() {
+            Some((start, end)) if start >= end => errors.push(format!("time_block.start must be before time_block.end, found {} to {}", self.time_block.start, self.time_block.end)),
+            Some(_) => (),
+            None => errors.push(format!("time_block.start and time_block.end must be times in the format HH:MM, found {} and {}", self.time_block.start, self.time_block.end)),
+        }
+        for b in self.time_block.breaks.iter() {
+            if parse_time_range(b).is_none() {
+                errors.push(format!("time_block.breaks must be in the format HH:MM-HH:MM, found {b}"));
+            }
+        }
+        if self.time_block.default_minutes < 1 {
+            errors.push(format!("time_block.default_minutes must be at least 1, found {}", self.time_block.default_minutes));
+        }
+        if self.calendar.busy_path.as_ref().is_some_and(|p| !p.exists()) {
+            errors.push(format!("calendar.busy_path must exist, found {}", self.calendar.busy_path.as_ref().unwrap().display()));
+        }
+        if let Some(path) = &self.calendar.export_path {
+            if path.parent().is_some_and(|p| !p.as_os_str().is_empty() && !p.is_dir()) {
+                errors.push(format!("calendar.export_path must be in an existing directory, found {}", path.display()));
+            }
+        }
+        if self.calendar.export_days > 30 {
+            errors.push(format!("calendar.export_days must be at most 30, found {}", self.calendar.export_days));
+        }
+        if self.escalation.after.is_some_and(|a| a < 1) {
+            errors.push("escalation.after must be at least 1".to_string());
+        }
+        if self.escalation.after.is_some() && !self.escalation.raise_priority && self.escalation.add_label.is_none()
+            && self.escalation.move_to_project.is_none() && self.escalation.comment.is_none() {
+            errors.push("escalation.after is set but no escalation is configured".to_string());
+        }
+        if self.goals.days_per_week < 1 || self.goals.days_per_week > 7 {
+            errors.push(format!("goals.days_per_week must be between 1 and 7, found {}", self.goals.days_per_week));
+        }
+        if !is_round_trip_date_format(&self.formats.date) {
+            errors.push(format!("formats.date must be a chrono format containing a full date, found {}", self.formats.date));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+// A date format is only usable if a date printed with it can be parsed back to the same date
+fn is_round_trip_date_format(format: &str) -> bool {
+    let sample = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+    let mut formatted = String::new();
+    if std::fmt::write(&mut formatted, format_args!("{}", sample.format(format))).is_err() {
+        return false;
+    }
+    NaiveDate::parse_from_str(&formatted, format) == Ok(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_all_errors_are_reported() {
+        let mut config = Config::default();
+        config.tasks.low_priority = 5;
+        config.goals.days_per_week = 0;
+        assert_eq!(config.validate().unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_token_source_needs_its_setting() {
+        let mut config = Config::default();
+        config.token.source = TokenSource::Command;
+        assert!(config.validate().is_err());
+        config.token.command = Some("echo token".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_weekday_minutes_override_daily() {
+        let capacity: CapacityConfig = toml::from_str("daily_minutes = 300\n[weekday_minutes]\nsaturday = 60").unwrap();
+        // The 2nd of August 2025 is a Saturday
+        assert_eq!(capacity.minutes_on(NaiveDate::from_ymd_opt(2025, 8, 2).unwrap()), 60);
+        assert_eq!(capacity.minutes_on(NaiveDate::from_ymd_opt(2025, 8, 4).unwrap()), 300);
+    }
+
+    #[test]
+    fn test_priority_limits_use_api_priorities() {
+        let priorities: PrioritiesConfig = toml::from_str("[limits]\np1 = 2\np2 = 4").unwrap();
+        assert_eq!(priorities.limits(), vec![(4, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn test_late_night_belongs_to_previous_day() {
+        let day = DayConfig { starts_at: "03:00".to_string() };
+        let date = NaiveDate::from_ymd_opt(2025, 7, 29).unwrap();
+        assert_eq!(day.day_of(date.and_hms_opt(2, 59, 0).unwrap()), date.pred_opt().unwrap());
+        assert_eq!(day.day_of(date.and_hms_opt(3, 0, 0).unwrap()), date);
+        assert_eq!(DayConfig::default().day_of(date.and_hms_opt(0, 0, 0).unwrap()), date);
+    }
+
+    #[test]
+    fn test_schedule_values_are_parsed() {
+        let schedule = ScheduleConfig {
+            non_working_weekdays: vec!["saturday".to_string(), "Sun".to_string()],
+            recurring_exclusions: vec!["12-25".to_string(), "02-29".to_string(), "13-01".to_string()],
+        };
+        assert_eq!(schedule.non_working_weekdays(), vec![Weekday::Sat, Weekday::Sun]);
+        assert_eq!(schedule.recurring_exclusions(), vec![(12, 25), (2, 29)]);
+    }
+
+    #[test]
+    fn test_date_format_must_round_trip() {
+        assert!(is_round_trip_date_format("%Y-%m-%d"));
+        assert!(is_round_trip_date_format("%d/%m/%Y"));
+        assert!(!is_round_trip_date_format("%Y-%m"));
+        assert!(!is_round_trip_date_format("%Q"));
+    }
+
+    #[test]
+    fn test_daily_cap() {
+        let mut config = Config::default();
+        assert_eq!(config.daily_cap(35), 5);
+        config.goals.days_per_week = 5;
+        assert_eq!(config.daily_cap(35), 7);
+    }
+}
@@ -1,6 +1,7 @@
-use chrono::{Days, Local, NaiveDate};
+use chrono::{Days, NaiveDate};
 use rusqlite::{params, Connection, Result};
 
+use config::settings::DbConfig;
 use db_lib::db_manager;
 
 struct ExcludedDay {
@@ -8,9 +9,9 @@ struct ExcludedDay {
     day: String,
 }
 
-pub fn get_excluded_days() -> Result<Vec<NaiveDate>> {
+pub fn get_excluded_days(config: &DbConfig) -> Result<Vec<NaiveDate>> {
     // Connect to SQLite database (creates the file if it doesn't exist)
-    let conn: Connection = db_manager::get_connection();
+    let conn: Connection = db_manager::get_connection(&config.database);
     create_table(&conn)?;
     
     let mut stmt = conn.prepare("SELECT id, day FROM excluded_days")?;
@@ -32,13 +33,13 @@ pub fn get_excluded_days() -> Result<Vec<NaiveDate>> {
     Ok(day_vec)
 }
 
-pub fn exclude_day(day: NaiveDate) -> Result<()> {
+pub fn exclude_day(day: NaiveDate, today: NaiveDate, config: &DbConfig) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
-    let conn: Connection = db_manager::get_connection();
+    let conn: Connection = db_manager::get_connection(&config.database);
     create_table(&conn)?;
 
     // First remove any unneeded days to keep it small
-    remove_old_days(&conn)?;
+    remove_old_days(&conn, today, config)?;
     
     // Add in the new day
     conn.execute(
@@ -49,10 +50,9 @@ pub fn exclude_day(day: NaiveDate) -> Result<()> {
     Ok(())
 }
 
-// Any day older than 7 days can be safely deleted
-fn remove_old_days(conn: &Connection) -> Result<()> {
-    let today:NaiveDate = Local::now().naive_local().date();
-    let limit = today.checked_sub_days(Days::new(7)).unwrap();
+// Any day older than the retention (7 days by default) can be safely deleted
+fn remove_old_days(conn: &Connection, today: NaiveDate, config: &DbConfig) -> Result<()> {
+    let limit = today.checked_sub_days(Days::new(config.excluded_days_retention)).unwrap();
 
     let mut stmt = conn.prepare("SELECT id, day FROM excluded_days")?;
     let day_iter = stmt.query_map([], |row| {
@@ -76,8 +76,8 @@ fn remove_old_days(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-pub fn purge() -> Result<()> {
-    let conn: Connection = db_manager::get_connection();
+pub fn purge(config: &DbConfig) -> Result<()> {
+    let conn: Connection = db_manager::get_connection(&config.database);
     conn.execute(
         "DROP TABLE IF EXISTS excluded_days",
         [], // No parameters needed
@@ -1,6 +1,7 @@
-use chrono::{Days, Local, NaiveDate};
+use chrono::{Days, NaiveDate, Weekday};
 use rusqlite::{params, Connection, Result}; // For database operations and result handling
 
+use config::settings::DbConfig;
 use db_lib::db_manager;
 
 struct ExcludedWeek {
@@ -8,9 +9,9 @@ struct ExcludedWeek {
     week_start: String,
 }
 
-pub fn get_excluded_weeks() -> Result<Vec<NaiveDate>> {
+pub fn get_excluded_weeks(config: &DbConfig) -> Result<Vec<NaiveDate>> {
     // Connect to SQLite database (creates the file if it doesn't exist)
-    let conn: Connection = db_manager::get_connection();
+    let conn: Connection = db_manager::get_connection(&config.database);
     create_table(&conn)?;
     
     let mut stmt = conn.prepare("SELECT id, week_start FROM excluded_weeks")?;
@@ -32,13 +33,18 @@ pub fn get_excluded_weeks() -> Result<Vec<NaiveDate>> {
     Ok(day_vec)
 }
 
-pub fn exclude_week(day: NaiveDate) -> Result<()> {
+// The first day of the week containing the day
+pub fn week_start_of(day: NaiveDate, week_start: Weekday) -> NaiveDate {
+    day.week(week_start).first_day()
+}
+
+pub fn exclude_week(day: NaiveDate, today: NaiveDate, week_start: Weekday, config: &DbConfig) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
-    let conn: Connection = db_manager::get_connection();
+    let conn: Connection = db_manager::get_connection(&config.database);
     create_table(&conn)?;
     
     // First remove any unneeded weeks
-    remove_old_weeks(&conn)?;
+    remove_old_weeks(&conn, today, week_start, config)?;
 
     // Add in the new excluded week
     conn.execute(
@@ -49,11 +55,9 @@ pub fn exclude_week(day: NaiveDate) -> Result<()> {
     Ok(())
 }
 
-// Any day older than 7 days can be safely deleted
-fn remove_old_weeks(conn: &Connection) -> Result<()> {
-    let today:NaiveDate = Local::now().naive_local().date();
-    // Calculate the day 5 weeks back, simplest calculation and always correct
-    let limit = today.checked_sub_days(Days::new(35)).unwrap();
+// Any week starting before the week the retention (5 weeks by default) reaches back to can be safely deleted
+fn remove_old_weeks(conn: &Connection, today: NaiveDate, week_start: Weekday, config: &DbConfig) -> Result<()> {
+    let limit = week_start_of(today.checked_sub_days(Days::new(config.excluded_weeks_retention)).unwrap(), week_start);
 
     let mut stmt = conn.prepare("SELECT id, week_start FROM excluded_weeks")?;
     let day_iter = stmt.query_map([], |row| {
@@ -77,8 +81,8 @@ fn remove_old_weeks(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-pub fn purge() -> Result<()> {
-    let conn: Connection = db_manager::get_connection();
+pub fn purge(config: &DbConfig) -> Result<()> {
+    let conn: Connection = db_manager::get_connection(&config.database);
     conn.execute(
         "DROP TABLE IF EXISTS excluded_weeks",
         [], // No parameters needed
@@ -97,4 +101,19 @@ fn create_table(conn: &Connection) -> Result<()> {
     )?;
 
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_week_start_of() {
+        // A Wednesday
+        let day = NaiveDate::from_ymd_opt(2025, 7, 30).unwrap();
+        assert_eq!(week_start_of(day, Weekday::Mon), NaiveDate::from_ymd_opt(2025, 7, 28).unwrap());
+        assert_eq!(week_start_of(day, Weekday::Sun), NaiveDate::from_ymd_opt(2025, 7, 27).unwrap());
+        assert_eq!(week_start_of(day, Weekday::Sat), NaiveDate::from_ymd_opt(2025, 7, 26).unwrap());
+        assert_eq!(week_start_of(day, Weekday::Wed), day);
+    }
 }
\ No newline at end of file
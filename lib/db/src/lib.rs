@@ -1,2 +1,4 @@
 pub mod exclude_days;
-pub mod exclude_weeks;
\ No newline at end of file
+pub mod exclude_weeks;
+pub mod postpone_counts;
+pub mod token_store;
\ No newline at end of file
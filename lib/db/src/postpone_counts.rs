@@ -0,0 +1,96 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, Result};
+use std::collections::HashMap;
+
+use config::settings::DbConfig;
+use db_lib::db_manager;
+
+#[derive(Debug)]
+pub struct PostponeCount {
+    pub task_id: String,
+    pub content: String,
+    pub count: i32,
+    pub last_postponed: NaiveDate,
+}
+
+pub fn get_postpone_counts(config: &DbConfig) -> Result<HashMap<String, i32>> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection(&config.database);
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT task_id, count FROM postpone_counts")?;
+    let count_iter = stmt.query_map([], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+
+    let mut counts: HashMap<String, i32> = HashMap::new();
+    for c in count_iter {
+        let (task_id, count) = c?;
+        counts.insert(task_id, count);
+    }
+    Ok(counts)
+}
+
+// The tasks postponed the most, most first
+pub fn get_most_postponed(limit: usize, config: &DbConfig) -> Result<Vec<PostponeCount>> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection(&config.database);
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT task_id, content, count, last_postponed FROM postpone_counts ORDER BY count DESC, last_postponed DESC LIMIT ?1")?;
+    let count_iter = stmt.query_map(params![limit as i64], |row| {
+        let last_postponed: String = row.get(3)?;
+        Ok(PostponeCount {
+            task_id: row.get(0)?,
+            content: row.get(1)?,
+            count: row.get(2)?,
+            last_postponed: NaiveDate::parse_from_str(&last_postponed, "%Y-%m-%d")
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+        })
+    })?;
+
+    count_iter.collect()
+}
+
+// Record that a task was postponed, returning the new count
+pub fn increment(task_id: &str, content: &str, today: NaiveDate, config: &DbConfig) -> Result<i32> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection(&config.database);
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO postpone_counts (task_id, content, count, last_postponed) VALUES (?1, ?2, 1, ?3)
+            ON CONFLICT(task_id) DO UPDATE SET count = count + 1, content = ?2, last_postponed = ?3",
+        params![task_id, content, today.format("%Y-%m-%d").to_string()],
+    )?;
+
+    conn.query_row(
+        "SELECT count FROM postpone_counts WHERE task_id = ?1",
+        params![task_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn purge(config: &DbConfig) -> Result<()> {
+    let conn: Connection = db_manager::get_connection(&config.database);
+    conn.execute(
+        "DROP TABLE IF EXISTS postpone_counts",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS postpone_counts (
+            task_id TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            last_postponed TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
\ No newline at end of file
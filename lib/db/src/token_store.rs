@@ -0,0 +1,64 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use config::settings::DbConfig;
+use db_lib::db_manager;
+
+// The encrypted token, encryption happens before it reaches the database
+pub struct StoredToken {
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+pub fn get_token(config: &DbConfig) -> Result<Option<StoredToken>> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection(&config.database);
+    create_table(&conn)?;
+
+    conn.query_row(
+        "SELECT nonce, ciphertext FROM encrypted_token WHERE id = 1",
+        [], // No parameters needed
+        |row| {
+            Ok(StoredToken {
+                nonce: row.get(0)?,
+                ciphertext: row.get(1)?
+            })
+        },
+    ).optional()
+}
+
+pub fn set_token(token: &StoredToken, config: &DbConfig) -> Result<()> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection(&config.database);
+    create_table(&conn)?;
+
+    // Only a single token is ever stored
+    conn.execute(
+        "INSERT OR REPLACE INTO encrypted_token (id, nonce, ciphertext) VALUES (1, ?1, ?2)",
+        params![token.nonce, token.ciphertext],
+    )?;
+
+    Ok(())
+}
+
+pub fn purge(config: &DbConfig) -> Result<()> {
+    let conn: Connection = db_manager::get_connection(&config.database);
+    conn.execute(
+        "DROP TABLE IF EXISTS encrypted_token",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS encrypted_token (
+            id INTEGER PRIMARY KEY,
+            nonce BLOB NOT NULL,
+            ciphertext BLOB NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
\ No newline at end of file
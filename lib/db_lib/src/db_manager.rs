@@ -1,17 +1,22 @@
 use rusqlite::{Connection};
 use directories::{ProjectDirs};
 use std::fs;
+use std::path::PathBuf;
 
-static DATABASE_NAME: &str = "todoist_productivity_tracker_database.db";
-
-pub fn get_connection() -> Connection {
+// The directory all local data is kept in, created if it doesn't exist
+pub fn get_data_dir() -> PathBuf {
     let binding = ProjectDirs::from("com", "everest", "todoist_productivity_tracker")
         .expect("Failed to get project directories");
     let data_dir =  binding.data_local_dir();
     if !fs::exists(data_dir).expect("Failed to check for directory") {
-        fs::create_dir(data_dir).expect("Failed to create directory");
+        fs::create_dir_all(data_dir).expect("Failed to create directory");
     }
-    let path = data_dir.join(DATABASE_NAME);
+    data_dir.to_path_buf()
+}
+
+// Each profile has its own database file in the data directory
+pub fn get_connection(database_name: &str) -> Connection {
+    let path = get_data_dir().join(database_name);
     let conn: Connection = Connection::open(path).expect("Failed to open a connection");
     conn
 }
\ No newline at end of file
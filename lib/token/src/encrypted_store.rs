@@ -0,0 +1,105 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use config::settings::DbConfig;
+use db::token_store::{self, StoredToken};
+use db_lib::db_manager;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// The token is encrypted with a key kept in a separate owner only file, so a copy of the database alone does not leak it
+pub fn load_token(config: &DbConfig) -> Result<String, String> {
+    let stored = token_store::get_token(config)
+        .map_err(|e| format!("Failed to read the token store: {e}"))?
+        .ok_or("No token has been stored, use the token store command first".to_string())?;
+    let key_path = key_path(config);
+    if !fs::exists(&key_path).map_err(|e| e.to_string())? {
+        return Err(format!("The token key file {} is missing, store the token again", key_path.display()));
+    }
+    let cipher = Aes256Gcm::new(&read_key(&key_path)?);
+    decrypt(&cipher, &stored)
+}
+
+pub fn save_token(token: &str, config: &DbConfig) -> Result<(), String> {
+    let key_path = key_path(config);
+    let key = if fs::exists(&key_path).map_err(|e| e.to_string())? {
+        read_key(&key_path)?
+    }
+    else {
+        create_key(&key_path)?
+    };
+    let stored = encrypt(&Aes256Gcm::new(&key), token)?;
+    token_store::set_token(&stored, config).map_err(|e| format!("Failed to write the token store: {e}"))
+}
+
+pub fn purge(config: &DbConfig) -> Result<(), String> {
+    token_store::purge(config).map_err(|e| format!("Failed to purge the token store: {e}"))?;
+    let key_path = key_path(config);
+    if fs::exists(&key_path).map_err(|e| e.to_string())? {
+        fs::remove_file(&key_path).map_err(|e| format!("Failed to remove {}: {e}", key_path.display()))?;
+    }
+    Ok(())
+}
+
+// The key sits beside the database it encrypts for, so each profile has its own
+fn key_path(config: &DbConfig) -> PathBuf {
+    db_manager::get_data_dir().join(config.database.to_owned() + ".key")
+}
+
+fn read_key(path: &Path) -> Result<Key<Aes256Gcm>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    if bytes.len() != 32 {
+        return Err(format!("The token key file {} is corrupt", path.display()));
+    }
+    Ok(*Key::<Aes256Gcm>::from_slice(&bytes))
+}
+
+fn create_key(path: &Path) -> Result<Key<Aes256Gcm>, String> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    file.write_all(key.as_slice()).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(key)
+}
+
+fn encrypt(cipher: &Aes256Gcm, token: &str) -> Result<StoredToken, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, token.as_bytes())
+        .map_err(|_| "Failed to encrypt the token".to_string())?;
+    Ok(StoredToken { nonce: nonce.to_vec(), ciphertext })
+}
+
+fn decrypt(cipher: &Aes256Gcm, stored: &StoredToken) -> Result<String, String> {
+    if stored.nonce.len() != 12 {
+        return Err("The stored token is corrupt".to_string());
+    }
+    let plaintext = cipher.decrypt(Nonce::from_slice(&stored.nonce), stored.ciphertext.as_ref())
+        .map_err(|_| "Failed to decrypt the stored token, the key file may have changed".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "The stored token is corrupt".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let cipher = Aes256Gcm::new(&Aes256Gcm::generate_key(OsRng));
+        let stored = encrypt(&cipher, "my-token").unwrap();
+        assert_ne!(stored.ciphertext, b"my-token");
+        assert_eq!(decrypt(&cipher, &stored).unwrap(), "my-token");
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let stored = encrypt(&Aes256Gcm::new(&Aes256Gcm::generate_key(OsRng)), "my-token").unwrap();
+        let other = Aes256Gcm::new(&Aes256Gcm::generate_key(OsRng));
+        assert!(decrypt(&other, &stored).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod encrypted_store;
+pub mod redact;
+pub mod token_provider;
\ No newline at end of file
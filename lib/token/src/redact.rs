@@ -0,0 +1,43 @@
+use std::sync::RwLock;
+
+static REDACTED: &str = "[REDACTED]";
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Register a value that must never appear in log output
+pub fn register_secret(secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    let mut secrets = SECRETS.write().expect("Secrets lock poisoned");
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+// Replace every registered secret in the message
+pub fn redact(message: &str) -> String {
+    let secrets = SECRETS.read().expect("Secrets lock poisoned");
+    let mut redacted = message.to_string();
+    for s in secrets.iter() {
+        redacted = redacted.replace(s.as_str(), REDACTED);
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registered_secret_is_redacted() {
+        register_secret("abc123secret");
+        assert_eq!(redact("Bearer abc123secret failed"), "Bearer [REDACTED] failed");
+        assert_eq!(redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn test_empty_secret_is_ignored() {
+        register_secret("");
+        assert_eq!(redact("unchanged"), "unchanged");
+    }
+}
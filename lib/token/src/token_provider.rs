@@ -0,0 +1,122 @@
+use config::settings::{DbConfig, TokenConfig, TokenSource};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{encrypted_store, redact};
+
+// The API token, never printed in full so it can't leak through debug output
+pub struct ApiToken(String);
+
+impl ApiToken {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ApiToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ApiToken([REDACTED])")
+    }
+}
+
+// Read the token from the configured source and register it so it is redacted from logs
+pub fn get_token(config: &TokenConfig, db_config: &DbConfig) -> Result<ApiToken, String> {
+    let raw = match config.source {
+        TokenSource::Env => from_env(&config.env_var)?,
+        TokenSource::File => from_file(config.file.as_deref().ok_or("token.file must be set for the file token source")?)?,
+        TokenSource::Command => from_command(config.command.as_deref().ok_or("token.command must be set for the command token source")?)?,
+        TokenSource::Store => encrypted_store::load_token(db_config)?,
+    };
+    let token = raw.trim().to_string();
+    if token.is_empty() {
+        return Err(format!("The {:?} token source returned an empty token", config.source));
+    }
+    redact::register_secret(&token);
+    log::debug!("Read the API token from the {:?} source", config.source);
+    Ok(ApiToken(token))
+}
+
+fn from_env(env_var: &str) -> Result<String, String> {
+    env::var(env_var).map_err(|_| format!("You need to set the environment variable {env_var} with your API key"))
+}
+
+fn from_file(path: &Path) -> Result<String, String> {
+    check_permissions(path)?;
+    fs::read_to_string(path).map_err(|e| format!("Failed to read the token file {}: {e}", path.display()))
+}
+
+// Refuse to use a token file that other users could read
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read the token file {}: {e}", path.display()))?;
+    let mode = metadata.permissions().mode();
+    if mode & 0o077 != 0 {
+        return Err(format!("The token file {} must only be accessible by its owner (chmod 600), found {:o}", path.display(), mode & 0o777));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+fn from_command(command: &str) -> Result<String, String> {
+    #[cfg(unix)]
+    let output = Command::new("sh").arg("-c").arg(command).output();
+    #[cfg(not(unix))]
+    let output = Command::new("cmd").arg("/C").arg(command).output();
+
+    let output = output.map_err(|e| format!("Failed to run the token command: {e}"))?;
+    if !output.status.success() {
+        // stderr is not included as password managers can echo the secret in their errors
+        return Err(format!("The token command failed with {}", output.status));
+    }
+    String::from_utf8(output.stdout).map_err(|_| "The token command did not return valid text".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_debug_is_redacted() {
+        let token = ApiToken("secret".to_string());
+        assert_eq!(format!("{:?}", token), "ApiToken([REDACTED])");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_source_trims_output() {
+        let config = TokenConfig {
+            source: TokenSource::Command,
+            command: Some("echo command-token".to_string()),
+            ..TokenConfig::default()
+        };
+        assert_eq!(get_token(&config, &DbConfig::default()).unwrap().expose(), "command-token");
+        assert_eq!(redact::redact("command-token"), "[REDACTED]");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_command_is_an_error() {
+        assert!(from_command("exit 3").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_readable_by_others_is_rejected() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = env::temp_dir().join(format!("todoist-tracker-token-{}", std::process::id()));
+        fs::write(&path, "file-token\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(from_file(&path).is_err());
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(from_file(&path).unwrap(), "file-token\n");
+        fs::remove_file(&path).unwrap();
+    }
+}